@@ -0,0 +1,4 @@
+// The migrations are embedded with sqlx::migrate!, so new ones have to trigger a rebuild
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
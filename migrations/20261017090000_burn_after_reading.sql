@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE clips ADD COLUMN burn_after_reading BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,22 @@
+-- Add migration script here
+-- Give every API key an id, so clips can refer to the key which created them
+CREATE TABLE api_keys_new (
+    key_id  TEXT PRIMARY KEY NOT NULL,
+    api_key BLOB UNIQUE NOT NULL,
+    admin   BOOLEAN NOT NULL DEFAULT 0
+);
+
+INSERT INTO api_keys_new (key_id, api_key)
+SELECT
+    lower(
+        hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' || substr(hex(randomblob(2)), 2) || '-'
+        || substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-'
+        || hex(randomblob(6))
+    ),
+    api_key
+FROM api_keys;
+
+DROP TABLE api_keys;
+ALTER TABLE api_keys_new RENAME TO api_keys;
+
+ALTER TABLE clips ADD COLUMN owner TEXT REFERENCES api_keys (key_id) ON DELETE SET NULL;
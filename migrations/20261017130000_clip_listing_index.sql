@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Listing the clips of an API key pages through them by posted date
+CREATE INDEX IF NOT EXISTS clips_owner_posted ON clips (owner, posted, clip_id);
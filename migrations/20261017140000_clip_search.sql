@@ -0,0 +1,7 @@
+-- Add migration script here
+-- Full-text index over the title and content of clips. Triggers can't read compressed content,
+-- so the data layer keeps the index in sync with the plain text of every clip. Being
+-- contentless, the index keeps no second copy of the text.
+CREATE VIRTUAL TABLE IF NOT EXISTS clips_fts USING fts5(title, content, content = '');
+
+INSERT INTO clips_fts (rowid, title, content) SELECT rowid, title, content FROM clips;
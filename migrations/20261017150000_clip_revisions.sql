@@ -0,0 +1,12 @@
+-- Add migration script here
+-- Append-only history of clips, holding every version which got replaced by an update
+CREATE TABLE IF NOT EXISTS clip_revisions (
+    clip_id  TEXT NOT NULL REFERENCES clips(clip_id) ON DELETE CASCADE,
+    revision BIGINT NOT NULL,
+    content  TEXT NOT NULL,
+    title    TEXT,
+    expires  DATETIME,
+    password TEXT,
+    archived DATETIME NOT NULL,
+    PRIMARY KEY (clip_id, revision)
+);
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- Uploaded files are stored next to the clip, its content holds the file name
+ALTER TABLE clips ADD COLUMN attachment BLOB;
+ALTER TABLE clips ADD COLUMN mime_type TEXT;
+ALTER TABLE clips ADD COLUMN filename TEXT;
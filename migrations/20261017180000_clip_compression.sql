@@ -0,0 +1,4 @@
+-- Add migration script here
+-- Large content is stored zlib compressed in `compressed_content`, which leaves `content` empty
+ALTER TABLE clips ADD COLUMN compressed_content BLOB;
+ALTER TABLE clip_revisions ADD COLUMN compressed_content BLOB;
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- The content of encrypted clips is ciphertext, the key only lives in the link to the clip
+ALTER TABLE clips ADD COLUMN encrypted BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- Existing clips were shared by link, which is what unlisted means
+ALTER TABLE clips ADD COLUMN visibility TEXT NOT NULL DEFAULT 'unlisted'
+    CHECK (visibility IN ('public', 'unlisted', 'private'));
+CREATE INDEX clips_public_posted ON clips (posted) WHERE visibility = 'public';
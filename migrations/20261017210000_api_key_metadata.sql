@@ -0,0 +1,13 @@
+-- Add migration script here
+-- Keep track of what each API key is for and what it may do
+ALTER TABLE api_keys ADD COLUMN name TEXT NOT NULL DEFAULT '';
+ALTER TABLE api_keys ADD COLUMN created DATETIME NOT NULL DEFAULT 0;
+ALTER TABLE api_keys ADD COLUMN last_used DATETIME;
+ALTER TABLE api_keys ADD COLUMN expires DATETIME;
+-- Comma separated list of read, write and admin
+ALTER TABLE api_keys ADD COLUMN scopes TEXT NOT NULL DEFAULT 'read,write';
+
+-- The keys were created some time before, now is the best guess there is
+UPDATE api_keys SET created = strftime('%s', 'now');
+UPDATE api_keys SET scopes = 'read,write,admin' WHERE admin;
+ALTER TABLE api_keys DROP COLUMN admin;
@@ -0,0 +1,7 @@
+-- Add migration script here
+-- Only a keyed hash of every API key is stored, together with a short prefix of the key to
+-- look it up by. The hash needs the secret of the server, so the keys which exist already are
+-- hashed when the server starts. Until then their prefix is NULL and key_hash holds the key.
+ALTER TABLE api_keys RENAME COLUMN api_key TO key_hash;
+ALTER TABLE api_keys ADD COLUMN prefix TEXT;
+CREATE INDEX api_keys_prefix ON api_keys (prefix);
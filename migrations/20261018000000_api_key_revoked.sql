@@ -0,0 +1,4 @@
+-- Add migration script here
+-- Revoked API keys are kept, so the clips they own keep their owner. A key is revoked once
+-- this holds the time it was revoked at.
+ALTER TABLE api_keys ADD COLUMN revoked DATETIME;
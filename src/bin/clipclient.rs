@@ -1,11 +1,18 @@
 use std::error::Error;
 
 use structopt::StructOpt;
+#[allow(unused_imports)]
 use strum::EnumString;
 
-use clishare::domain::clip::field::{Content, Expires, Password, ShortCode, Title};
-use clishare::service::ask::{GetClip, NewClip, UpdateClip};
-use clishare::web::api::{ApiKey, API_KEY_HEADER};
+use clishare::domain::api_key::Scope;
+use clishare::domain::clip::field::{
+    BurnAfterReading, Content, Encrypted, Expires, Language, ManageToken, MaxHits, Owner, Password,
+    ShortCode, Title, Visibility,
+};
+use clishare::domain::clip::ClipPage;
+use clishare::domain::encryption::EncryptionKey;
+use clishare::service::ask::{GetClip, ListCursor, NewApiKey, NewClip, SortOrder, UpdateClip};
+use clishare::web::api::{ApiKey, IssuedApiKey, RevokeApiKey, API_KEY_HEADER, PASSWORD_HEADER};
 use clishare::Clip;
 
 #[derive(StructOpt, Debug)]
@@ -14,16 +21,49 @@ enum Command {
         shortcode: ShortCode,
         #[structopt(short, long, help = "password")]
         password: Option<String>,
+        #[structopt(short, long, help = "key of an encrypted clip, or its whole link")]
+        key: Option<EncryptionKey>,
     },
     New {
         #[structopt(help = "content")]
         clip: String,
         #[structopt(short, long, help = "title")]
         title: Option<Title>,
-        #[structopt(short, long, help = "expiraition date")]
+        #[structopt(
+            short,
+            long,
+            help = "expiration, relative (10m, 3h, 7d) or a date (2024-09-19, RFC 3339)"
+        )]
         expires: Option<Expires>,
         #[structopt(short, long, help = "password")]
         password: Option<Password>,
+        #[structopt(long, help = "delete the clip the first time it is read")]
+        burn_after_reading: bool,
+        #[structopt(long, help = "maximum number of views")]
+        max_hits: Option<MaxHits>,
+        #[structopt(
+            short,
+            long,
+            help = "language for syntax highlighting, detected if not set"
+        )]
+        language: Option<Language>,
+        #[structopt(
+            long,
+            help = "encrypt the content locally, the key is only part of the link"
+        )]
+        encrypt: bool,
+        #[structopt(
+            long,
+            parse(try_from_str = ShortCode::custom),
+            help = "custom shortcode, like deploy-notes, generated if not set"
+        )]
+        shortcode: Option<ShortCode>,
+        #[structopt(
+            long,
+            help = "public, unlisted (default) or private to the API key",
+            default_value
+        )]
+        visibility: Visibility,
     },
     Update {
         shortcode: ShortCode,
@@ -31,10 +71,50 @@ enum Command {
         clip: String,
         #[structopt(short, long, help = "title")]
         title: Option<Title>,
-        #[structopt(short, long, help = "expiraition date")]
+        #[structopt(
+            short,
+            long,
+            help = "expiration, relative (10m, 3h, 7d) or a date (2024-09-19, RFC 3339)"
+        )]
         expires: Option<Expires>,
         #[structopt(short, long, help = "password")]
         password: Option<Password>,
+        #[structopt(short, long, help = "key of an encrypted clip, or its whole link")]
+        key: Option<EncryptionKey>,
+    },
+    Delete {
+        shortcode: ShortCode,
+    },
+    List {
+        #[structopt(short, long, help = "number of clips per page")]
+        limit: Option<u32>,
+        #[structopt(short, long, help = "cursor of the page to fetch")]
+        cursor: Option<ListCursor>,
+        #[structopt(short, long, help = "sort order, newest or oldest")]
+        sort: Option<SortOrder>,
+    },
+    Key(KeyCommand),
+}
+
+#[derive(StructOpt, Debug)]
+enum KeyCommand {
+    /// Issue a new API key, needs an admin key
+    Create {
+        #[structopt(short, long, help = "what the key is for", default_value = "")]
+        name: String,
+        #[structopt(
+            short,
+            long = "scope",
+            help = "read, write or admin, repeat for several scopes [default: read and write]"
+        )]
+        scopes: Vec<Scope>,
+        #[structopt(short, long, help = "expiration, a date (2024-09-19) or RFC 3339")]
+        expires: Option<clishare::Time>,
+    },
+    /// Revoke an API key, admin keys may revoke any key
+    Revoke {
+        #[structopt(help = "the key to revoke, in base64")]
+        key: ApiKey,
     },
 }
 
@@ -49,12 +129,24 @@ struct Opt {
     api_key: ApiKey,
 }
 
+/// Turn an unsuccessful response into an error. The API explains most errors with a JSON
+/// string, anything else in the body is shown as is next to the status.
+fn response_error(response: reqwest::blocking::Response) -> Box<dyn Error> {
+    let status = response.status();
+    let body = response.text().unwrap_or_default();
+    match serde_json::from_str::<String>(&body) {
+        Ok(message) => message.into(),
+        Err(_) if body.trim().is_empty() => status.to_string().into(),
+        Err(_) => format!("{}: {}", status, body.trim()).into(),
+    }
+}
+
 fn get_clip(addr: &str, ask_service: GetClip, api_key: ApiKey) -> Result<Clip, Box<dyn Error>> {
     let client = reqwest::blocking::Client::builder().build()?;
     let addr = format!("{}/api/clip/{}", addr, ask_service.shortcode.into_inner());
     let mut request = client.get(addr);
     request = match ask_service.password.into_inner() {
-        Some(password) => request.header(reqwest::header::COOKIE, format!("password={}", password)),
+        Some(password) => request.header(PASSWORD_HEADER, password),
         None => request,
     };
 
@@ -62,12 +154,35 @@ fn get_clip(addr: &str, ask_service: GetClip, api_key: ApiKey) -> Result<Clip, B
     Ok(request.send()?.json()?)
 }
 
+/// Read a clip of the API key without counting a view, so it can be updated
+fn get_owned_clip(
+    addr: &str,
+    shortcode: &ShortCode,
+    api_key: ApiKey,
+) -> Result<Clip, Box<dyn Error>> {
+    let client = reqwest::blocking::Client::builder().build()?;
+    let addr = format!("{}/api/clip/{}/edit", addr, shortcode.as_str());
+    let request = client.get(addr).header(API_KEY_HEADER, api_key.to_base64());
+    let response = request.send()?;
+    if response.status().is_success() {
+        Ok(response.json()?)
+    } else {
+        Err(response_error(response))
+    }
+}
+
 fn new_clip(addr: &str, ask_service: NewClip, api_key: ApiKey) -> Result<Clip, Box<dyn Error>> {
     let client = reqwest::blocking::Client::builder().build()?;
     let addr = format!("{}/api/clip", addr);
     let mut request = client.post(addr);
     request = request.header(API_KEY_HEADER, api_key.to_base64());
-    Ok(request.json(&ask_service).send()?.json()?)
+    let response = request.json(&ask_service).send()?;
+    if response.status().is_success() {
+        Ok(response.json()?)
+    } else {
+        // Taken or invalid shortcodes are explained by the server
+        Err(response_error(response))
+    }
 }
 
 fn update_clip(
@@ -82,17 +197,103 @@ fn update_clip(
     Ok(request.json(&ask_service).send()?.json()?)
 }
 
+fn delete_clip(addr: &str, shortcode: ShortCode, api_key: ApiKey) -> Result<(), Box<dyn Error>> {
+    let client = reqwest::blocking::Client::builder().build()?;
+    let addr = format!("{}/api/clip/{}", addr, shortcode.into_inner());
+    let mut request = client.delete(addr);
+    request = request.header(API_KEY_HEADER, api_key.to_base64());
+    let response = request.send()?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(response_error(response))
+    }
+}
+
+fn create_api_key(
+    addr: &str,
+    ask_service: NewApiKey,
+    api_key: ApiKey,
+) -> Result<IssuedApiKey, Box<dyn Error>> {
+    let client = reqwest::blocking::Client::builder().build()?;
+    let addr = format!("{}/api/clip/key", addr);
+    let mut request = client.post(addr);
+    request = request.header(API_KEY_HEADER, api_key.to_base64());
+    let response = request.json(&ask_service).send()?;
+    if response.status().is_success() {
+        Ok(response.json()?)
+    } else {
+        Err(response_error(response))
+    }
+}
+
+fn revoke_api_key(addr: &str, revoked: ApiKey, api_key: ApiKey) -> Result<(), Box<dyn Error>> {
+    let client = reqwest::blocking::Client::builder().build()?;
+    let addr = format!("{}/api/clip/key", addr);
+    let mut request = client.delete(addr);
+    request = request.header(API_KEY_HEADER, api_key.to_base64());
+    let req = RevokeApiKey {
+        key: revoked.to_base64(),
+    };
+    let response = request.json(&req).send()?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(response_error(response))
+    }
+}
+
+fn list_clips(
+    addr: &str,
+    limit: Option<u32>,
+    cursor: Option<ListCursor>,
+    sort: Option<SortOrder>,
+    api_key: ApiKey,
+) -> Result<ClipPage, Box<dyn Error>> {
+    let client = reqwest::blocking::Client::builder().build()?;
+    let addr = format!("{}/api/clip", addr);
+    let mut query = vec![];
+    if let Some(limit) = limit {
+        query.push(("limit", limit.to_string()));
+    }
+    if let Some(cursor) = cursor {
+        query.push(("cursor", cursor.to_string()));
+    }
+    if let Some(sort) = sort {
+        query.push(("sort", sort.to_string()));
+    }
+    let mut request = client.get(addr).query(&query);
+    request = request.header(API_KEY_HEADER, api_key.to_base64());
+    let response = request.send()?;
+    if response.status().is_success() {
+        Ok(response.json()?)
+    } else {
+        Err(response_error(response))
+    }
+}
+
 fn run(opt: Opt) -> Result<(), Box<dyn Error>> {
     match opt.command {
         Command::Get {
             shortcode,
             password,
+            key,
         } => {
             let req = GetClip {
-                password: Password::new(password.unwrap_or_default())?,
+                password: Password::new(password.unwrap_or_default()),
                 shortcode,
+                unlock: None,
+                reader: None,
             };
-            let clip = get_clip(opt.addr.as_str(), req, opt.api_key)?;
+            let mut clip = get_clip(opt.addr.as_str(), req, opt.api_key)?;
+            if clip.encrypted.is_set() {
+                match key {
+                    Some(key) => {
+                        clip.content = Content::new(&key.decrypt(clip.content.as_str())?)?;
+                    }
+                    None => eprintln!("the clip is encrypted, pass --key to decrypt it"),
+                }
+            }
             println!("{:#?}", clip);
             Ok(())
         }
@@ -102,15 +303,43 @@ fn run(opt: Opt) -> Result<(), Box<dyn Error>> {
             title,
             expires,
             password,
+            burn_after_reading,
+            max_hits,
+            language,
+            encrypt,
+            shortcode,
+            visibility,
         } => {
+            let key = encrypt.then(EncryptionKey::generate);
+            let content = match &key {
+                Some(key) => key.encrypt(clip.as_str()),
+                None => clip,
+            };
             let req = NewClip {
-                content: Content::new(clip.as_str())?,
+                content: Content::new(content.as_str())?,
                 title: title.unwrap_or_default(),
                 expires: expires.unwrap_or_default(),
                 password: password.unwrap_or_default(),
+                burn_after_reading: BurnAfterReading::new(burn_after_reading),
+                max_hits: max_hits.unwrap_or_default(),
+                language: language.unwrap_or_default(),
+                encrypted: Encrypted::new(key.is_some()),
+                shortcode,
+                visibility,
+                attachment: None,
+                owner: Owner::default(),
+                manage_token: ManageToken::default(),
             };
             let clip = new_clip(opt.addr.as_str(), req, opt.api_key)?;
             println!("{:#?}", clip);
+            if let Some(key) = key {
+                println!(
+                    "link: {}/clip/{}#{}",
+                    opt.addr,
+                    clip.shortcode.as_str(),
+                    key
+                );
+            }
             Ok(())
         }
 
@@ -120,15 +349,19 @@ fn run(opt: Opt) -> Result<(), Box<dyn Error>> {
             title,
             expires,
             password,
+            key,
         } => {
             let password = password.unwrap_or_default();
-            let service_req = GetClip {
-                password: password.clone(),
-                shortcode: shortcode.clone(),
+            // Reading the clip the usual way would count a view, or even burn it
+            let original_clip = get_owned_clip(opt.addr.as_str(), &shortcode, opt.api_key.clone())?;
+            // The new content of an encrypted clip has to be encrypted with the same key
+            let content = match (original_clip.encrypted.is_set(), key) {
+                (true, Some(key)) => key.encrypt(clip.as_str()),
+                (true, None) => return Err("the clip is encrypted, pass its --key".into()),
+                (false, _) => clip,
             };
-            let original_clip = get_clip(opt.addr.as_str(), service_req, opt.api_key.clone())?;
             let service_req = UpdateClip {
-                content: Content::new(clip.as_str())?,
+                content: Content::new(content.as_str())?,
                 expires: expires.unwrap_or(original_clip.expires),
                 title: title.unwrap_or(original_clip.title),
                 password,
@@ -138,6 +371,53 @@ fn run(opt: Opt) -> Result<(), Box<dyn Error>> {
             println!("{:#?}", clip);
             Ok(())
         }
+
+        Command::Delete { shortcode } => {
+            delete_clip(opt.addr.as_str(), shortcode, opt.api_key)?;
+            println!("clip deleted");
+            Ok(())
+        }
+
+        Command::List {
+            limit,
+            cursor,
+            sort,
+        } => {
+            let page = list_clips(opt.addr.as_str(), limit, cursor, sort, opt.api_key)?;
+            println!("{:#?}", page.clips);
+            if let Some(cursor) = page.next_cursor {
+                println!("next page: --cursor {}", cursor);
+            }
+            Ok(())
+        }
+
+        Command::Key(KeyCommand::Create {
+            name,
+            scopes,
+            expires,
+        }) => {
+            let defaults = NewApiKey::default();
+            let req = NewApiKey {
+                name,
+                scopes: if scopes.is_empty() {
+                    defaults.scopes
+                } else {
+                    scopes
+                },
+                expires,
+            };
+            let issued = create_api_key(opt.addr.as_str(), req, opt.api_key)?;
+            println!("{:#?}", issued.info);
+            // The server doesn't keep the key, it can't be shown again
+            println!("key: {}", issued.key);
+            Ok(())
+        }
+
+        Command::Key(KeyCommand::Revoke { key }) => {
+            revoke_api_key(opt.addr.as_str(), key, opt.api_key)?;
+            println!("revoked");
+            Ok(())
+        }
     }
 }
 fn main() {
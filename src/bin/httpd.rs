@@ -1,11 +1,100 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use dotenv::dotenv;
 use structopt::StructOpt;
 
-use clishare::data::AppDatabase;
+use clishare::data::compression::{self, Compression};
+use clishare::data::query::RevocationStatus;
+use clishare::data::{AppDatabase, DbId};
+use clishare::domain::api_key::{ApiKeyInfo, Scope};
+use clishare::domain::clip::field::ShortCodeFormat;
+use clishare::domain::clip::limits::Limits;
 use clishare::domain::maintenance::Maintenance;
-use clishare::web::{hit_counter::HitCounter, renderer::Renderer};
+use clishare::service::{action, ask};
+use clishare::web::api::{ApiKey, KeySecret};
+use clishare::web::{highlight::Highlighter, hit_counter::HitCounter, renderer::Renderer};
+use clishare::{DataError, ServiceError, ShortCode};
+
+/// What httpd should do, serving is the default
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Run the web server
+    Serve,
+    /// Inspect the database schema
+    Migrate(MigrateCommand),
+    #[structopt(flatten)]
+    Admin(AdminCommand),
+}
+
+/// Administrative commands, run directly against the migrated database instead of serving it
+#[derive(Debug, StructOpt)]
+enum AdminCommand {
+    /// Manage API keys without going through the API
+    Key(KeyCommand),
+    /// Deal with stored clips
+    Clip(ClipCommand),
+}
+
+#[derive(Debug, StructOpt)]
+enum KeyCommand {
+    /// Issue a new API key, use it to bootstrap the first admin key
+    Create {
+        #[structopt(short, long, help = "what the key is for", default_value = "")]
+        name: String,
+        #[structopt(
+            short,
+            long = "scope",
+            number_of_values = 1,
+            help = "read, write or admin, repeat for several scopes [default: read and write]"
+        )]
+        scopes: Vec<Scope>,
+        #[structopt(short, long, help = "expiration, a date (2024-09-19) or RFC 3339")]
+        expires: Option<clishare::Time>,
+    },
+    /// List the stored API keys, the keys themselves are never shown
+    List,
+    /// Revoke an API key
+    Revoke {
+        #[structopt(help = "the id of the key as listed, or the key itself in base64")]
+        key: KeyRef,
+    },
+}
+
+/// An API key given either by its id or as the key itself
+#[derive(Debug)]
+enum KeyRef {
+    Id(DbId),
+    Key(ApiKey),
+}
+
+impl FromStr for KeyRef {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match DbId::from_str(s) {
+            Ok(key_id) => Ok(Self::Id(key_id)),
+            Err(_) => ApiKey::from_str(s)
+                .map(Self::Key)
+                .map_err(|_| "neither a key id nor an API key".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+enum MigrateCommand {
+    /// List the migrations built into httpd and whether they have been applied
+    Status,
+    /// Apply the pending migrations, hashing API keys stored in the clear by earlier versions
+    Run,
+}
+
+#[derive(Debug, StructOpt)]
+enum ClipCommand {
+    /// Delete a clip regardless of its owner, password or visibility
+    Purge { shortcode: ShortCode },
+    /// Show how many clips are stored and how they are configured
+    Stats,
+}
 
 /// Command line options
 #[derive(Debug, StructOpt)]
@@ -15,6 +104,36 @@ struct Opt {
     connection_string: String,
     #[structopt(short, long, parse(from_os_str), default_value = "templates/")]
     template_directory: PathBuf,
+    /// Largest clip content or uploaded file in bytes [default: 4 MiB]
+    #[structopt(long)]
+    max_content_size: Option<usize>,
+    /// Longest clip title in characters [default: 200]
+    #[structopt(long)]
+    max_title_length: Option<usize>,
+    /// Longest clip password in characters [default: 128]
+    #[structopt(long)]
+    max_password_length: Option<usize>,
+    /// Compress clip content larger than this many bytes [default: 64 KiB]
+    #[structopt(long)]
+    compression_threshold: Option<usize>,
+    /// Length of generated shortcodes [default: 10]
+    #[structopt(long)]
+    shortcode_length: Option<usize>,
+    /// Letters and digits generated shortcodes are made of [default: a-z and 0-9]
+    #[structopt(long)]
+    shortcode_alphabet: Option<String>,
+    /// Leave characters which are easily mistaken for one another, like 0/o and 1/l, out of
+    /// generated shortcodes
+    #[structopt(long)]
+    unambiguous_shortcodes: bool,
+    /// Secret API keys are hashed with, changing it invalidates every API key
+    #[structopt(long, env = "API_KEY_SECRET", hide_env_values = true)]
+    api_key_secret: Option<String>,
+    /// Don't apply pending database migrations at startup, refuse to start while any are pending
+    #[structopt(long)]
+    no_migrate: bool,
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
 fn main() {
@@ -28,19 +147,107 @@ fn main() {
     // Create a handel (access to executor) to runtime so we can pass it around
     let handle = rt.handle().clone();
 
+    let defaults = Limits::default();
+    let limits = Limits {
+        content: opt.max_content_size.unwrap_or(defaults.content),
+        title: opt.max_title_length.unwrap_or(defaults.title),
+        password: opt.max_password_length.unwrap_or(defaults.password),
+    };
+
+    let shortcode_format = match ShortCodeFormat::new(
+        opt.shortcode_length
+            .unwrap_or(ShortCodeFormat::DEFAULT_LENGTH),
+        opt.shortcode_alphabet
+            .as_deref()
+            .unwrap_or(ShortCodeFormat::DEFAULT_ALPHABET),
+        opt.unambiguous_shortcodes,
+    ) {
+        Ok(format) => format,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let compression = Compression::new(
+        opt.compression_threshold
+            .unwrap_or(compression::DEFAULT_THRESHOLD),
+    );
+
     let renderer = Renderer::new(opt.template_directory.clone());
+    let highlighter = Highlighter::new();
 
     // run a future and block a thread until the future complete
-    let database = rt.block_on(async move { AppDatabase::new(&opt.connection_string).await });
+    let connection_string = opt.connection_string.clone();
+    let database = match rt.block_on(async move { AppDatabase::new(&connection_string).await }) {
+        Ok(database) => database,
+        Err(e) => {
+            eprintln!("failed to open the database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // The migrate commands are done before anything else touches the database
+    let command = match opt.command {
+        None | Some(Command::Serve) => None,
+        Some(Command::Admin(command)) => Some(command),
+        // Looking at the migrations must not apply them
+        Some(Command::Migrate(MigrateCommand::Status)) => {
+            if let Err(e) = rt.block_on(print_migration_status(&database)) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(Command::Migrate(MigrateCommand::Run)) => {
+            let key_secret = key_secret(opt.api_key_secret.as_deref());
+            rt.block_on(migrate(&database, &key_secret));
+            return;
+        }
+    };
+
+    let key_secret = key_secret(opt.api_key_secret.as_deref());
+    if !opt.no_migrate {
+        rt.block_on(migrate(&database, &key_secret));
+    } else {
+        // Without its schema, a new or outdated database would fail every request instead
+        match rt.block_on(database.pending_migrations()) {
+            Ok(0) => (),
+            Ok(pending) => {
+                eprintln!(
+                    "{} database migrations are pending, apply them with `httpd migrate run`",
+                    pending
+                );
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("failed to read the database migrations: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(command) = command {
+        if let Err(e) = rt.block_on(run_command(command, &key_secret, &database)) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     let hit_counter = HitCounter::new(database.get_pool().clone(), handle.clone());
-    let maintenance = Maintenance::spawn(database.get_pool().clone(), handle);
+    let maintenance = Maintenance::spawn(database.get_pool().clone(), compression, handle);
 
     let config = clishare::RocketConfig {
         renderer,
+        highlighter,
         database,
         hit_counter,
         maintenance,
+        limits,
+        shortcode_format,
+        compression,
+        key_secret,
     };
 
     rt.block_on(async move {
@@ -50,3 +257,149 @@ fn main() {
             .expect("failed to launch rocket server")
     });
 }
+
+/// The secret API keys are hashed with. Hashes keyed with an empty secret would be as good as
+/// the keys themselves, so httpd refuses to run without one.
+fn key_secret(secret: Option<&str>) -> KeySecret {
+    match secret {
+        Some(secret) if !secret.is_empty() => KeySecret::new(secret.as_bytes()),
+        _ => {
+            eprintln!("API_KEY_SECRET must be set, API keys are stored hashed with it");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Apply the pending migrations, httpd can't go on if that fails
+async fn migrate(database: &AppDatabase, secret: &KeySecret) {
+    match database.migrate(secret).await {
+        Ok(migrated) => {
+            if migrated.migrations > 0 {
+                println!("applied {} database migrations", migrated.migrations);
+            }
+            if migrated.api_keys > 0 {
+                println!("hashed {} API keys stored in the clear", migrated.api_keys);
+            }
+        }
+        Err(e) => {
+            eprintln!("failed to migrate the database: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Run one of the administrative commands directly against the database
+async fn run_command(
+    command: AdminCommand,
+    secret: &KeySecret,
+    database: &AppDatabase,
+) -> Result<(), ServiceError> {
+    let pool = database.get_pool();
+    match command {
+        AdminCommand::Key(KeyCommand::Create {
+            name,
+            scopes,
+            expires,
+        }) => {
+            let defaults = ask::NewApiKey::default();
+            let req = ask::NewApiKey {
+                name,
+                scopes: if scopes.is_empty() {
+                    defaults.scopes
+                } else {
+                    scopes
+                },
+                expires,
+            };
+            let api_key = action::create_api_key(req, secret, pool).await?;
+            print_api_key(&action::get_api_key_info(api_key.clone(), secret, pool).await?);
+            // Only the hash is stored, the key can't be shown again
+            println!("key: {}", api_key.to_base64());
+        }
+
+        AdminCommand::Key(KeyCommand::List) => {
+            let keys = action::list_api_keys(pool).await?;
+            if keys.is_empty() {
+                println!("no API keys");
+            }
+            keys.iter().for_each(print_api_key);
+        }
+
+        AdminCommand::Key(KeyCommand::Revoke { key }) => {
+            let status = match key {
+                KeyRef::Id(key_id) => action::revoke_api_key_by_id(&key_id, pool).await?,
+                KeyRef::Key(api_key) => action::revoke_api_key(api_key, None, secret, pool).await?,
+            };
+            match status {
+                RevocationStatus::Revoked => println!("revoked"),
+                RevocationStatus::NotFound => return Err(ServiceError::NotFound),
+            }
+        }
+
+        AdminCommand::Clip(ClipCommand::Purge { shortcode }) => {
+            action::purge_clip(&shortcode, pool).await?;
+            println!("purged {}", shortcode.as_str());
+        }
+
+        AdminCommand::Clip(ClipCommand::Stats) => {
+            let stats = action::clip_stats(pool).await?;
+            println!("clips:        {}", stats.clips);
+            println!("  public:     {}", stats.public);
+            println!("  unlisted:   {}", stats.unlisted);
+            println!("  private:    {}", stats.private);
+            println!("  protected:  {}", stats.protected);
+            println!("  encrypted:  {}", stats.encrypted);
+            println!("  files:      {}", stats.attachments);
+            println!("  compressed: {}", stats.compressed);
+            println!("  expired:    {}", stats.expired);
+            println!("revisions:    {}", stats.revisions);
+            println!("hits:         {}", stats.hits);
+            println!("stored bytes: {}", stats.stored_bytes);
+            println!("API keys:     {}", stats.api_keys);
+        }
+    }
+    Ok(())
+}
+
+async fn print_migration_status(database: &AppDatabase) -> Result<(), DataError> {
+    let status = database.migration_status().await?;
+    for migration in &status {
+        let state = match (migration.applied, migration.changed) {
+            (false, _) => "pending",
+            (true, false) => "applied",
+            (true, true) => "applied, changed since",
+        };
+        println!(
+            "{}  {:<24} {}",
+            migration.version, migration.description, state
+        );
+    }
+    let pending = status.iter().filter(|migration| !migration.applied).count();
+    println!("{} of {} migrations pending", pending, status.len());
+    Ok(())
+}
+
+fn print_api_key(info: &ApiKeyInfo) {
+    let format_time = |time: &Option<clishare::Time>, none: &str| match time {
+        Some(time) => time
+            .clone()
+            .into_inner()
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+        None => none.to_owned(),
+    };
+    let scopes: Vec<&str> = info.scopes.iter().map(Scope::as_str).collect();
+    println!(
+        "{}  {:<20} scopes: {:<16} created: {}  last used: {}  expires: {}",
+        info.key_id,
+        if info.name.is_empty() {
+            "-"
+        } else {
+            &info.name
+        },
+        scopes.join(","),
+        info.created.clone().into_inner().format("%Y-%m-%d %H:%M"),
+        format_time(&info.last_used, "never"),
+        format_time(&info.expires, "never"),
+    );
+}
@@ -1,3 +1,4 @@
+pub mod compression;
 pub mod model;
 pub mod query;
 
@@ -5,13 +6,49 @@ use std::str::FromStr;
 
 use derive_more::{Display, From};
 use serde::{Deserialize, Serialize};
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::sqlite::SqliteConnectOptions;
 use sqlx::Sqlite;
 use uuid::Uuid;
 
+use crate::web::api::KeySecret;
+
 #[derive(Debug, thiserror::Error)]
 pub enum DataError {
     #[error("database error: {0}")]
     Database(#[from] sqlx::Error),
+    #[error("invalid id: {0}")]
+    Id(#[from] uuid::Error),
+    #[error("the shortcode {0} is already taken")]
+    ShortCodeTaken(String),
+    #[error("migration error: {0}")]
+    Migrate(#[from] MigrateError),
+    #[error("clip error: {0}")]
+    Clip(#[from] crate::ClipError),
+    #[error("API keys stored in the clear can't be hashed without a key secret")]
+    MissingKeySecret,
+}
+
+/// The migrations in ./migrations, embedded into the binary when it's compiled
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Whether one of the embedded migrations has been applied to the database
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    // The migration was applied, but its SQL has been edited since
+    pub changed: bool,
+}
+
+/// What [`Database::migrate`] changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Migrated {
+    /// Number of migrations which were applied
+    pub migrations: usize,
+    /// Number of API keys stored in the clear which were hashed
+    pub api_keys: u64,
 }
 
 // Type alias for easier DBMS switch
@@ -25,28 +62,78 @@ pub type AppQueryResult = sqlx::sqlite::SqliteQueryResult;
 pub struct Database<D: sqlx::Database>(sqlx::Pool<D>);
 
 impl Database<Sqlite> {
-    pub async fn new(connection_str: &str) -> Self {
+    /// Connect to the database, a missing database file gets created
+    pub async fn new(connection_str: &str) -> Result<Self, DataError> {
+        let options = SqliteConnectOptions::from_str(connection_str)?.create_if_missing(true);
         let pool = sqlx::sqlite::SqlitePoolOptions::new()
-            .connect(connection_str)
-            .await;
-        match pool {
-            Ok(pool) => Self(pool),
-            Err(e) => {
-                eprintln!("{}\n", e);
-                eprintln!(
-                    "If the database has not yet been created, run: \n    $ sqlx database setup\n"
-                );
-                panic!("database connection error")
-            }
-        }
+            .connect_with(options)
+            .await?;
+        Ok(Self(pool))
     }
 
     pub fn get_pool(&self) -> &DatabasePool {
         &self.0
     }
+
+    /// Apply the embedded migrations which are still pending.
+    ///
+    /// API keys stored in the clear by earlier versions are hashed afterwards, that part of the
+    /// migration needs the `secret` keys are hashed with, which SQL can't know.
+    pub async fn migrate(&self, secret: &KeySecret) -> Result<Migrated, DataError> {
+        let pending = self.pending_migrations().await?;
+        MIGRATOR.run(&self.0).await?;
+        let api_keys = query::hash_legacy_api_keys(secret, &self.0).await?;
+        Ok(Migrated {
+            migrations: pending,
+            api_keys,
+        })
+    }
+
+    /// Count the embedded migrations which have not been applied to the database yet
+    pub async fn pending_migrations(&self) -> Result<usize, DataError> {
+        Ok(self
+            .migration_status()
+            .await?
+            .iter()
+            .filter(|status| !status.applied)
+            .count())
+    }
+
+    /// Compare the embedded migrations with the ones applied to the database, without
+    /// changing the database
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>, DataError> {
+        let mut conn = self.0.acquire().await?;
+        // sqlx keeps track of the applied migrations in a table it creates on the first run
+        let tracked: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+        )
+        .fetch_one(&mut conn)
+        .await?;
+        let applied = match tracked {
+            0 => vec![],
+            _ => conn.list_applied_migrations().await?,
+        };
+
+        Ok(MIGRATOR
+            .iter()
+            .map(|migration| {
+                let found = applied
+                    .iter()
+                    .find(|applied| applied.version == migration.version);
+                MigrationStatus {
+                    version: migration.version,
+                    description: migration.description.to_string(),
+                    applied: found.is_some(),
+                    changed: found
+                        .map(|applied| applied.checksum != migration.checksum)
+                        .unwrap_or(false),
+                }
+            })
+            .collect())
+    }
 }
 
-#[derive(Clone, Debug, From, Display, Deserialize, Serialize)]
+#[derive(Clone, Debug, From, Display, Deserialize, Serialize, PartialEq, Eq)]
 pub struct DbId(Uuid);
 
 impl DbId {
@@ -89,15 +176,33 @@ pub mod test {
     use tokio::runtime::Handle;
 
     pub fn new_db(handle: &Handle) -> AppDatabase {
-        use sqlx::migrate::Migrator;
-        use std::path::Path;
-
         handle.block_on(async move {
-            let db = Database::new(":memory:").await;
-            let migrator = Migrator::new(Path::new("./migrations")).await.unwrap();
-            let pool = db.get_pool();
-            migrator.run(pool).await.unwrap();
+            let db = Database::new(":memory:").await.unwrap();
+            db.migrate(&KeySecret::default()).await.unwrap();
             db
         })
     }
+
+    #[test]
+    fn test_migration_status() {
+        let rt = crate::test::async_runtime();
+
+        rt.block_on(async move {
+            let db = Database::new(":memory:").await.unwrap();
+            let status = db.migration_status().await.unwrap();
+            assert_eq!(status.len(), MIGRATOR.iter().count());
+            assert!(status.iter().all(|migration| !migration.applied));
+
+            assert_eq!(db.pending_migrations().await.unwrap(), status.len());
+
+            let secret = KeySecret::default();
+            assert_eq!(db.migrate(&secret).await.unwrap().migrations, status.len());
+            assert_eq!(db.pending_migrations().await.unwrap(), 0);
+            let status = db.migration_status().await.unwrap();
+            assert!(status
+                .iter()
+                .all(|migration| migration.applied && !migration.changed));
+            assert_eq!(db.migrate(&secret).await.unwrap(), Migrated::default());
+        });
+    }
 }
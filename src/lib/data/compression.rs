@@ -0,0 +1,71 @@
+use std::io::{Read, Write};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use crate::ClipError;
+
+pub const DEFAULT_THRESHOLD: usize = 64 * 1024;
+
+/// When content gets stored compressed, configured once when the server starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    /// Size in bytes above which content is stored compressed
+    pub threshold: usize,
+}
+
+impl Compression {
+    pub fn new(threshold: usize) -> Self {
+        Self { threshold }
+    }
+
+    /// The zlib compressed content, for content above the threshold which shrinks when it is
+    /// compressed. Any other content is stored as it is.
+    pub(in crate::data) fn compress(&self, content: &str) -> Option<Vec<u8>> {
+        if content.len() <= self.threshold {
+            return None;
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        // Writing into a Vec can't fail
+        let compressed = encoder
+            .write_all(content.as_bytes())
+            .and_then(|_| encoder.finish())
+            .expect("failed to compress content");
+        Some(compressed).filter(|compressed| compressed.len() < content.len())
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::new(DEFAULT_THRESHOLD)
+    }
+}
+
+/// Restore content compressed by [`Compression::compress`].
+pub(in crate::data) fn decompress(data: &[u8]) -> Result<String, ClipError> {
+    let mut content = String::new();
+    ZlibDecoder::new(data)
+        .read_to_string(&mut content)
+        .map_err(|e| ClipError::CorruptContent(e.to_string()))?;
+    Ok(content)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compress_round_trip() {
+        let log = "GET /clip/raw/abc 200\n".repeat(DEFAULT_THRESHOLD);
+        let compressed = Compression::default().compress(&log).unwrap();
+        assert!(compressed.len() < log.len() / 10);
+        assert_eq!(decompress(&compressed).unwrap(), log);
+    }
+
+    #[test]
+    fn test_small_content_stays_plain() {
+        assert!(Compression::default().compress("hello").is_none());
+        assert!(Compression::new(2).compress("hello").is_none());
+        assert!(decompress(b"not zlib").is_err());
+    }
+}
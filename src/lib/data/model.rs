@@ -2,7 +2,8 @@ use std::convert::TryFrom;
 
 use chrono::{NaiveDateTime, Utc};
 
-use crate::data::DbId;
+use crate::data::{DataError, DbId};
+#[allow(unused_imports)]
 use crate::domain::clip::field::Posted;
 use crate::{ClipError, ShortCode, Time};
 
@@ -17,28 +18,154 @@ pub struct Clip {
     pub(in crate::data) expires: Option<NaiveDateTime>,
     pub(in crate::data) password: Option<String>,
     pub(in crate::data) hits: i64,
+    pub(in crate::data) burn_after_reading: bool,
+    pub(in crate::data) max_hits: Option<i64>,
+    pub(in crate::data) owner: Option<String>,
+    pub(in crate::data) manage_token: Option<String>,
+    pub(in crate::data) language: Option<String>,
+    pub(in crate::data) attachment: Option<Vec<u8>>,
+    pub(in crate::data) mime_type: Option<String>,
+    pub(in crate::data) filename: Option<String>,
+    pub(in crate::data) encrypted: bool,
+    pub(in crate::data) visibility: String,
+    pub(in crate::data) compressed_content: Option<Vec<u8>>,
 }
 
 impl TryFrom<Clip> for crate::domain::Clip {
     type Error = ClipError;
 
     fn try_from(clip: Clip) -> Result<Self, Self::Error> {
+        use crate::data::compression;
         use crate::domain::clip::field;
         use std::str::FromStr;
 
         Ok(Self {
             clip_id: field::ClipId::new(DbId::from_str(clip.clip_id.as_str())?),
             shortcode: field::ShortCode::from(clip.shortcode.as_str()),
-            content: field::Content::new(clip.content.as_str())?,
-            title: field::Title::new(clip.title),
+            content: field::Content::from_stored(match clip.compressed_content {
+                Some(data) => compression::decompress(&data)?,
+                None => clip.content,
+            }),
+            title: field::Title::from_stored(clip.title),
             posted: field::Posted::new(Time::from_naive_utc(clip.posted)),
             expires: field::Expires::new(clip.expires.map(Time::from_naive_utc)),
-            password: field::Password::new(clip.password.unwrap_or_default())?,
+            password: field::Password::from_stored(clip.password),
             hits: field::Hits::new(u64::try_from(clip.hits)?),
+            max_hits: field::MaxHits::new(clip.max_hits.map(u64::try_from).transpose()?)?,
+            burn_after_reading: field::BurnAfterReading::new(clip.burn_after_reading),
+            owner: field::Owner::new(clip.owner.as_deref().map(DbId::from_str).transpose()?),
+            manage_token: field::ManageToken::new(clip.manage_token),
+            language: field::Language::new(clip.language)?,
+            encrypted: field::Encrypted::new(clip.encrypted),
+            visibility: field::Visibility::from_str(&clip.visibility)?,
+            attachment: match (clip.attachment, clip.mime_type) {
+                (Some(data), Some(mime_type)) => Some(field::Attachment::from_parts(
+                    data,
+                    mime_type,
+                    clip.filename,
+                )),
+                _ => None,
+            },
         })
     }
 }
 
+/// Metadata of a clip without its content, as it is listed
+#[derive(Debug, sqlx::FromRow)]
+pub struct ClipSummary {
+    pub(in crate::data) clip_id: String,
+    pub(in crate::data) shortcode: String,
+    pub(in crate::data) title: Option<String>,
+    pub(in crate::data) posted: NaiveDateTime,
+    pub(in crate::data) expires: Option<NaiveDateTime>,
+    pub(in crate::data) hits: i64,
+    pub(in crate::data) protected: bool,
+    pub(in crate::data) visibility: String,
+}
+
+impl ClipSummary {
+    /// Cursor of the page which continues after this clip
+    pub fn cursor(&self) -> Result<crate::service::ask::ListCursor, ClipError> {
+        use std::str::FromStr;
+
+        Ok(crate::service::ask::ListCursor {
+            posted: self.posted.and_utc().timestamp(),
+            clip_id: DbId::from_str(&self.clip_id)?,
+        })
+    }
+}
+
+impl TryFrom<ClipSummary> for crate::domain::clip::ClipSummary {
+    type Error = ClipError;
+
+    fn try_from(clip: ClipSummary) -> Result<Self, Self::Error> {
+        use crate::domain::clip::field;
+        use std::str::FromStr;
+
+        Ok(Self {
+            shortcode: field::ShortCode::from(clip.shortcode.as_str()),
+            title: field::Title::from_stored(clip.title),
+            posted: field::Posted::new(Time::from_naive_utc(clip.posted)),
+            expires: field::Expires::new(clip.expires.map(Time::from_naive_utc)),
+            hits: field::Hits::new(u64::try_from(clip.hits)?),
+            protected: clip.protected,
+            visibility: field::Visibility::from_str(&clip.visibility)?,
+        })
+    }
+}
+
+/// Clip revision that directly converted from sqlx::Row
+#[derive(Debug, sqlx::FromRow)]
+pub struct ClipRevision {
+    pub(in crate::data) revision: i64,
+    pub(in crate::data) content: String,
+    pub(in crate::data) title: Option<String>,
+    pub(in crate::data) expires: Option<NaiveDateTime>,
+    pub(in crate::data) archived: NaiveDateTime,
+    pub(in crate::data) compressed_content: Option<Vec<u8>>,
+}
+
+impl TryFrom<ClipRevision> for crate::domain::clip::ClipRevision {
+    type Error = ClipError;
+
+    fn try_from(revision: ClipRevision) -> Result<Self, Self::Error> {
+        use crate::data::compression;
+        use crate::domain::clip::field;
+
+        Ok(Self {
+            revision: u32::try_from(revision.revision)?,
+            content: field::Content::from_stored(match revision.compressed_content {
+                Some(data) => compression::decompress(&data)?,
+                None => revision.content,
+            }),
+            title: field::Title::from_stored(revision.title),
+            expires: field::Expires::new(revision.expires.map(Time::from_naive_utc)),
+            archived: Time::from_naive_utc(revision.archived),
+        })
+    }
+}
+
+/// Title and content of a clip as they are stored, to keep the search index in sync with
+#[derive(Debug, sqlx::FromRow)]
+pub struct IndexedClip {
+    pub(in crate::data) rowid: i64,
+    pub(in crate::data) title: Option<String>,
+    pub(in crate::data) content: String,
+    pub(in crate::data) compressed_content: Option<Vec<u8>>,
+}
+
+impl IndexedClip {
+    /// The content as it is found in the search index
+    pub(in crate::data) fn indexed_content(&self) -> Result<String, ClipError> {
+        use crate::data::compression;
+
+        match &self.compressed_content {
+            Some(data) => compression::decompress(data),
+            None => Ok(self.content.clone()),
+        }
+    }
+}
+
 /// Use shortcode to query a clip
 pub struct GetClip {
     pub(in crate::data) shortcode: String,
@@ -71,26 +198,54 @@ impl From<String> for GetClip {
 /// New clip with hits = 0, and other specify by user
 pub struct NewClip {
     pub(in crate::data) clip_id: String,
-    pub(in crate::data) shortcode: String,
+    // Generated when the creator didn't choose one
+    pub(in crate::data) shortcode: Option<String>,
     pub(in crate::data) content: String,
     pub(in crate::data) title: Option<String>,
     // Store the dates in database as number of seconds instead of doing string convertion
     pub(in crate::data) posted: i64,
     pub(in crate::data) expires: Option<i64>,
     pub(in crate::data) password: Option<String>,
+    pub(in crate::data) burn_after_reading: bool,
+    pub(in crate::data) max_hits: Option<i64>,
+    pub(in crate::data) owner: Option<String>,
+    pub(in crate::data) manage_token: Option<String>,
+    pub(in crate::data) language: Option<String>,
+    pub(in crate::data) attachment: Option<Vec<u8>>,
+    pub(in crate::data) mime_type: Option<String>,
+    pub(in crate::data) filename: Option<String>,
+    pub(in crate::data) encrypted: bool,
+    pub(in crate::data) visibility: String,
 }
 
 // Service layer -> Data layer
 impl From<crate::service::ask::NewClip> for NewClip {
     fn from(req: crate::service::ask::NewClip) -> Self {
+        let (attachment, mime_type, filename) = match req.attachment.map(|a| a.into_parts()) {
+            Some((data, mime_type, filename)) => (Some(data), Some(mime_type), filename),
+            None => (None, None, None),
+        };
         Self {
             clip_id: DbId::new().into(),
-            shortcode: ShortCode::default().into(),
+            shortcode: req.shortcode.map(ShortCode::into_inner),
             content: req.content.into_inner(),
             title: req.title.into_inner(),
             posted: Utc::now().timestamp(),
             expires: req.expires.into_inner().map(|time| time.timestamp()),
             password: req.password.into_inner(),
+            burn_after_reading: req.burn_after_reading.into_inner(),
+            max_hits: req
+                .max_hits
+                .into_inner()
+                .map(|max_hits| i64::try_from(max_hits).unwrap_or(i64::MAX)),
+            owner: req.owner.into_inner().map(String::from),
+            manage_token: req.manage_token.into_inner(),
+            language: req.language.into_inner(),
+            encrypted: req.encrypted.into_inner(),
+            visibility: req.visibility.to_string(),
+            attachment,
+            mime_type,
+            filename,
         }
     }
 }
@@ -116,3 +271,159 @@ impl From<crate::service::ask::UpdateClip> for UpdateClip {
         }
     }
 }
+
+/// One page of the clips of an owner, starting after the cursor
+pub struct ListClips {
+    pub(in crate::data) owner: String,
+    pub(in crate::data) posted: i64,
+    pub(in crate::data) clip_id: String,
+    pub(in crate::data) limit: i64,
+    pub(in crate::data) newest_first: bool,
+}
+
+// Service layer -> Data layer
+impl From<crate::service::ask::ListClips> for ListClips {
+    fn from(req: crate::service::ask::ListClips) -> Self {
+        use crate::service::ask::SortOrder;
+
+        let newest_first = req.sort == SortOrder::Newest;
+        // Without a cursor, start before the first clip in the requested order
+        let (posted, clip_id) = match req.cursor {
+            Some(cursor) => (cursor.posted, cursor.clip_id.into()),
+            None if newest_first => (i64::MAX, String::new()),
+            None => (i64::MIN, String::new()),
+        };
+        Self {
+            owner: req.owner.into_inner().map(String::from).unwrap_or_default(),
+            posted,
+            clip_id,
+            // one extra row tells whether there is a next page
+            limit: i64::from(req.limit) + 1,
+            newest_first,
+        }
+    }
+}
+
+/// Full-text search over the clips which are not expired
+pub struct SearchClips {
+    pub(in crate::data) query: String,
+    pub(in crate::data) owner: String,
+    pub(in crate::data) now: i64,
+    pub(in crate::data) limit: i64,
+}
+
+// Service layer -> Data layer
+impl From<crate::service::ask::SearchClips> for SearchClips {
+    fn from(req: crate::service::ask::SearchClips) -> Self {
+        Self {
+            query: req.query,
+            owner: req.owner.into_inner().map(String::from).unwrap_or_default(),
+            now: Utc::now().timestamp(),
+            limit: i64::from(req.limit),
+        }
+    }
+}
+
+/// API key identity that directly converted from sqlx::Row
+#[derive(Debug, sqlx::FromRow)]
+pub struct ApiKey {
+    pub(in crate::data) key_id: String,
+    pub(in crate::data) key_hash: Vec<u8>,
+    pub(in crate::data) name: String,
+    pub(in crate::data) created: NaiveDateTime,
+    pub(in crate::data) last_used: Option<NaiveDateTime>,
+    pub(in crate::data) expires: Option<NaiveDateTime>,
+    pub(in crate::data) scopes: String,
+    pub(in crate::data) revoked: Option<NaiveDateTime>,
+}
+
+impl TryFrom<ApiKey> for crate::domain::api_key::ApiKeyInfo {
+    type Error = DataError;
+
+    fn try_from(api_key: ApiKey) -> Result<Self, Self::Error> {
+        use crate::domain::api_key::Scope;
+        use std::str::FromStr;
+
+        Ok(Self {
+            key_id: DbId::from_str(api_key.key_id.as_str())?,
+            name: api_key.name,
+            created: Time::from_naive_utc(api_key.created),
+            last_used: api_key.last_used.map(Time::from_naive_utc),
+            expires: api_key.expires.map(Time::from_naive_utc),
+            revoked: api_key.revoked.map(Time::from_naive_utc),
+            // Scopes this version doesn't know about grant nothing
+            scopes: api_key
+                .scopes
+                .split(',')
+                .filter_map(|scope| Scope::from_str(scope).ok())
+                .collect(),
+        })
+    }
+}
+
+/// New API key, its secret is passed along separately
+pub struct NewApiKey {
+    pub(in crate::data) key_id: String,
+    pub(in crate::data) name: String,
+    pub(in crate::data) created: i64,
+    pub(in crate::data) expires: Option<i64>,
+    pub(in crate::data) scopes: String,
+}
+
+// Service layer -> Data layer
+impl From<crate::service::ask::NewApiKey> for NewApiKey {
+    fn from(req: crate::service::ask::NewApiKey) -> Self {
+        Self {
+            key_id: DbId::new().into(),
+            name: req.name,
+            created: Utc::now().timestamp(),
+            expires: req.expires.map(|time| time.timestamp()),
+            scopes: req
+                .scopes
+                .iter()
+                .map(|scope| scope.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// Numbers about the stored clips that directly converted from sqlx::Row
+#[derive(Debug, sqlx::FromRow)]
+pub struct ClipStats {
+    pub(in crate::data) clips: i64,
+    pub(in crate::data) public: i64,
+    pub(in crate::data) unlisted: i64,
+    pub(in crate::data) private: i64,
+    pub(in crate::data) protected: i64,
+    pub(in crate::data) encrypted: i64,
+    pub(in crate::data) attachments: i64,
+    pub(in crate::data) compressed: i64,
+    pub(in crate::data) expired: i64,
+    pub(in crate::data) hits: i64,
+    pub(in crate::data) stored_bytes: i64,
+    pub(in crate::data) revisions: i64,
+    pub(in crate::data) api_keys: i64,
+}
+
+impl TryFrom<ClipStats> for crate::domain::clip::ClipStats {
+    type Error = ClipError;
+
+    fn try_from(stats: ClipStats) -> Result<Self, Self::Error> {
+        Ok(Self {
+            clips: u64::try_from(stats.clips)?,
+            public: u64::try_from(stats.public)?,
+            unlisted: u64::try_from(stats.unlisted)?,
+            private: u64::try_from(stats.private)?,
+            protected: u64::try_from(stats.protected)?,
+            encrypted: u64::try_from(stats.encrypted)?,
+            attachments: u64::try_from(stats.attachments)?,
+            compressed: u64::try_from(stats.compressed)?,
+            expired: u64::try_from(stats.expired)?,
+            hits: u64::try_from(stats.hits)?,
+            stored_bytes: u64::try_from(stats.stored_bytes)?,
+            revisions: u64::try_from(stats.revisions)?,
+            api_keys: u64::try_from(stats.api_keys)?,
+        })
+    }
+}
@@ -1,13 +1,16 @@
-use sqlx::Row;
-
 use super::model::{self, UpdateClip};
-use crate::data::{DataError, DatabasePool};
-use crate::web::api::ApiKey;
+use crate::data::compression::Compression;
+use crate::data::{DataError, DatabasePool, DbId, Transaction};
+use crate::domain::clip::field::{Password, ShortCodeFormat};
+use crate::web::api::{ApiKey, KeySecret};
 use crate::ShortCode;
 
 // alias Result so we don't need to manual type 'DataError' everytime
 type Result<T> = std::result::Result<T, DataError>;
 
+/// How often a new clip is inserted with a freshly generated shortcode before giving up
+const SHORTCODE_ATTEMPTS: usize = 5;
+
 pub async fn get_clip<M: Into<model::GetClip>>(
     model: M,
     pool: &DatabasePool,
@@ -23,59 +26,365 @@ pub async fn get_clip<M: Into<model::GetClip>>(
     .await?)
 }
 
+/// Insert a new clip, generating its shortcode in `format` unless one was chosen.
 pub async fn new_clip<M: Into<model::NewClip>>(
     model: M,
+    format: &ShortCodeFormat,
+    compression: &Compression,
     pool: &DatabasePool,
 ) -> Result<model::Clip> {
     let model = model.into();
-    let _ = sqlx::query!(
-        r#"INSERT INTO clips (
-            clip_id,
+    let compressed_content = compression.compress(&model.content);
+    // Compressed content leaves the text column empty
+    let content = if compressed_content.is_some() {
+        ""
+    } else {
+        model.content.as_str()
+    };
+    let mut attempt = 1;
+    let shortcode = loop {
+        let shortcode = match &model.shortcode {
+            Some(shortcode) => shortcode.clone(),
+            None => format.generate().into_inner(),
+        };
+        let mut transaction = pool.begin().await?;
+        let inserted = sqlx::query!(
+            r#"INSERT INTO clips (
+                clip_id,
+                shortcode,
+                content,
+                title,
+                posted,
+                expires,
+                password,
+                hits,
+                burn_after_reading,
+                max_hits,
+                owner,
+                manage_token,
+                language,
+                attachment,
+                mime_type,
+                filename,
+                encrypted,
+                visibility,
+                compressed_content
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+            model.clip_id,
             shortcode,
             content,
-            title,
-            posted,
-            expires,
-            password,
-            hits
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#,
-        model.clip_id,
-        model.shortcode,
-        model.content,
-        model.title,
-        model.posted,
-        model.expires,
-        model.password,
-        0
-    )
-    .execute(pool)
-    .await?;
-    get_clip(model.shortcode, pool).await
+            model.title,
+            model.posted,
+            model.expires,
+            model.password,
+            0,
+            model.burn_after_reading,
+            model.max_hits,
+            model.owner,
+            model.manage_token,
+            model.language,
+            model.attachment,
+            model.mime_type,
+            model.filename,
+            model.encrypted,
+            model.visibility,
+            compressed_content
+        )
+        .execute(&mut transaction)
+        .await;
+        match inserted {
+            Ok(done) => {
+                let rowid = done.last_insert_rowid();
+                index_clip(rowid, &model.title, &model.content, &mut transaction).await?;
+                transaction.commit().await?;
+                break shortcode;
+            }
+            // Generated shortcodes are replaced when they collide, chosen ones are not
+            Err(sqlx::Error::Database(ref db)) if db.message().contains("clips.shortcode") => {
+                if model.shortcode.is_some() || attempt == SHORTCODE_ATTEMPTS {
+                    return Err(DataError::ShortCodeTaken(shortcode));
+                }
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    get_clip(shortcode, pool).await
 }
 
+/// Update a clip, keeping the version it replaces as a new revision.
+///
+/// The revision is written in the same transaction as the update, so no version gets lost.
 pub async fn update_clip<M: Into<UpdateClip>>(
     model: M,
+    compression: &Compression,
     pool: &DatabasePool,
 ) -> Result<model::Clip> {
     let model = model.into();
+    let compressed_content = compression.compress(&model.content);
+    let content = if compressed_content.is_some() {
+        ""
+    } else {
+        model.content.as_str()
+    };
+    let mut transaction = pool.begin().await?;
+    sqlx::query!(
+        r#"INSERT INTO clip_revisions
+            (clip_id, revision, content, title, expires, password, archived, compressed_content)
+            SELECT clip_id,
+                   COALESCE((SELECT MAX(revision) FROM clip_revisions
+                             WHERE clip_revisions.clip_id = clips.clip_id), 0) + 1,
+                   content, title, expires, password, strftime('%s', 'now'), compressed_content
+            FROM clips WHERE shortcode = ?"#,
+        model.shortcode
+    )
+    .execute(&mut transaction)
+    .await?;
+    let replaced = sqlx::query_as!(
+        model::IndexedClip,
+        r#"SELECT rowid AS "rowid!: i64", title, content, compressed_content
+           FROM clips WHERE shortcode = ?"#,
+        model.shortcode
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+    unindex_clips(&replaced, &mut transaction).await?;
     let _ = sqlx::query!(
         r#"UPDATE clips SET 
             content  = ?,
+            compressed_content = ?,
             expires = ?,
             title = ?,
             password = ?
             WHERE shortcode = ?"#,
-        model.content,
+        content,
+        compressed_content,
         model.expires,
         model.title,
         model.password,
         model.shortcode
     )
-    .execute(pool)
+    .execute(&mut transaction)
     .await?;
+    for clip in replaced.iter() {
+        index_clip(clip.rowid, &model.title, &model.content, &mut transaction).await?;
+    }
+    transaction.commit().await?;
     get_clip(model.shortcode, pool).await
 }
 
+/// Add a clip to the search index, with its plain content.
+async fn index_clip(
+    rowid: i64,
+    title: &Option<String>,
+    content: &str,
+    transaction: &mut Transaction<'_>,
+) -> Result<()> {
+    sqlx::query("INSERT INTO clips_fts (rowid, title, content) VALUES (?, ?, ?)")
+        .bind(rowid)
+        .bind(title)
+        .bind(content)
+        .execute(&mut *transaction)
+        .await?;
+    Ok(())
+}
+
+/// Remove clips from the search index. Being contentless, the index has to be handed the
+/// text it holds for a clip to remove it.
+async fn unindex_clips(
+    clips: &[model::IndexedClip],
+    transaction: &mut Transaction<'_>,
+) -> Result<()> {
+    for clip in clips {
+        sqlx::query(
+            "INSERT INTO clips_fts (clips_fts, rowid, title, content) VALUES ('delete', ?, ?, ?)",
+        )
+        .bind(clip.rowid)
+        .bind(&clip.title)
+        .bind(clip.indexed_content()?)
+        .execute(&mut *transaction)
+        .await?;
+    }
+    Ok(())
+}
+
+/// All revisions of a clip, oldest first.
+pub async fn list_revisions(
+    shortcode: &ShortCode,
+    pool: &DatabasePool,
+) -> Result<Vec<model::ClipRevision>> {
+    let shortcode = shortcode.as_str();
+    Ok(sqlx::query_as!(
+        model::ClipRevision,
+        r#"SELECT clip_revisions.revision, clip_revisions.content, clip_revisions.title,
+                  clip_revisions.expires, clip_revisions.archived,
+                  clip_revisions.compressed_content
+           FROM clip_revisions JOIN clips ON clips.clip_id = clip_revisions.clip_id
+           WHERE clips.shortcode = ?
+           ORDER BY clip_revisions.revision"#,
+        shortcode
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn get_revision(
+    shortcode: &ShortCode,
+    revision: u32,
+    pool: &DatabasePool,
+) -> Result<model::ClipRevision> {
+    let shortcode = shortcode.as_str();
+    Ok(sqlx::query_as!(
+        model::ClipRevision,
+        r#"SELECT clip_revisions.revision, clip_revisions.content, clip_revisions.title,
+                  clip_revisions.expires, clip_revisions.archived,
+                  clip_revisions.compressed_content
+           FROM clip_revisions JOIN clips ON clips.clip_id = clip_revisions.clip_id
+           WHERE clips.shortcode = ? AND clip_revisions.revision = ?"#,
+        shortcode,
+        revision
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+pub async fn update_password(
+    shortcode: &ShortCode,
+    password: Password,
+    pool: &DatabasePool,
+) -> Result<()> {
+    let shortcode = shortcode.as_str();
+    let password = password.into_inner();
+    Ok(sqlx::query!(
+        "UPDATE clips SET password = ? WHERE shortcode = ?",
+        password,
+        shortcode
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())?)
+}
+
+/// Delete a clip, returning the number of deleted rows.
+///
+/// Only one of several concurrent callers will see a non-zero count, which makes this
+/// usable for burning a clip after it has been read.
+pub async fn delete_clip(shortcode: &ShortCode, pool: &DatabasePool) -> Result<u64> {
+    let mut transaction = pool.begin().await?;
+    let deleted = sqlx::query_as::<_, model::IndexedClip>(
+        "DELETE FROM clips WHERE shortcode = ?
+         RETURNING rowid, title, content, compressed_content",
+    )
+    .bind(shortcode.as_str())
+    .fetch_all(&mut transaction)
+    .await?;
+    unindex_clips(&deleted, &mut transaction).await?;
+    transaction.commit().await?;
+    Ok(deleted.len() as u64)
+}
+
+/// List one page of the clips of an owner, ordered by the date they were posted.
+///
+/// Expired clips and clips which reached their view limit are left out, they are about to be
+/// deleted.
+pub async fn list_clips<M: Into<model::ListClips>>(
+    model: M,
+    pool: &DatabasePool,
+) -> Result<Vec<model::ClipSummary>> {
+    let model = model.into();
+    let clips = if model.newest_first {
+        sqlx::query_as!(
+            model::ClipSummary,
+            r#"SELECT clip_id, shortcode, title, posted, expires, hits,
+                      password IS NOT NULL AS "protected!: bool", visibility
+               FROM clips
+               WHERE owner = ? AND (posted, clip_id) < (?, ?)
+                 AND (expires IS NULL OR expires > strftime('%s', 'now'))
+                 AND (max_hits IS NULL OR hits < max_hits)
+               ORDER BY posted DESC, clip_id DESC LIMIT ?"#,
+            model.owner,
+            model.posted,
+            model.clip_id,
+            model.limit
+        )
+        .fetch_all(pool)
+        .await?
+    } else {
+        sqlx::query_as!(
+            model::ClipSummary,
+            r#"SELECT clip_id, shortcode, title, posted, expires, hits,
+                      password IS NOT NULL AS "protected!: bool", visibility
+               FROM clips
+               WHERE owner = ? AND (posted, clip_id) > (?, ?)
+                 AND (expires IS NULL OR expires > strftime('%s', 'now'))
+                 AND (max_hits IS NULL OR hits < max_hits)
+               ORDER BY posted ASC, clip_id ASC LIMIT ?"#,
+            model.owner,
+            model.posted,
+            model.clip_id,
+            model.limit
+        )
+        .fetch_all(pool)
+        .await?
+    };
+    Ok(clips)
+}
+
+/// Search the title and content of clips, best matches first.
+///
+/// Password protected and private clips only show up for their owner, while expired clips and
+/// clips which reached their view limit never do.
+pub async fn search_clips<M: Into<model::SearchClips>>(
+    model: M,
+    pool: &DatabasePool,
+) -> Result<Vec<model::ClipSummary>> {
+    let model = model.into();
+    Ok(sqlx::query_as!(
+        model::ClipSummary,
+        r#"SELECT clips.clip_id AS "clip_id!", clips.shortcode AS "shortcode!", clips.title,
+                  clips.posted AS "posted!", clips.expires, clips.hits AS "hits!",
+                  clips.password IS NOT NULL AS "protected!: bool",
+                  clips.visibility AS "visibility!"
+           FROM clips_fts JOIN clips ON clips.rowid = clips_fts.rowid
+           WHERE clips_fts MATCH ?
+             AND NOT clips.burn_after_reading
+             AND ((clips.password IS NULL AND clips.visibility = 'public'
+                   AND clips.max_hits IS NULL) OR clips.owner = ?)
+             AND (clips.expires IS NULL OR clips.expires > ?)
+             AND (clips.max_hits IS NULL OR clips.hits < clips.max_hits)
+           ORDER BY clips_fts.rank LIMIT ?"#,
+        model.query,
+        model.owner,
+        model.now,
+        model.limit
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// List the most recently posted public clips which anyone can read.
+///
+/// Password protected, encrypted and burn after reading clips are left out, just like expired
+/// clips and clips which reached their view limit.
+pub async fn recent_clips(limit: u32, pool: &DatabasePool) -> Result<Vec<model::ClipSummary>> {
+    Ok(sqlx::query_as!(
+        model::ClipSummary,
+        r#"SELECT clip_id, shortcode, title, posted, expires, hits,
+                  password IS NOT NULL AS "protected!: bool", visibility
+           FROM clips
+           WHERE visibility = 'public'
+             AND password IS NULL
+             AND NOT encrypted
+             AND NOT burn_after_reading
+             AND (expires IS NULL OR expires > strftime('%s', 'now'))
+             AND (max_hits IS NULL OR hits < max_hits)
+           ORDER BY posted DESC, clip_id DESC LIMIT ?"#,
+        limit
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
 pub async fn increase_hit_count(
     shortcode: &ShortCode,
     hits: u32,
@@ -92,54 +401,309 @@ pub async fn increase_hit_count(
     .map(|_| ())?) // ? here is for turning sqlx error to DataError
 }
 
-pub async fn save_api_key(api_key: ApiKey, pool: &DatabasePool) -> Result<ApiKey> {
-    let bytes = api_key.clone().into_inner();
-    sqlx::query!("INSERT INTO api_keys (api_key) VALUES (?)", bytes)
-        .execute(pool)
-        .await
-        .map(|_| ())?;
+/// Count a single hit on a clip with a view limit, bypassing the buffered [`HitCounter`].
+///
+/// Returns `false` when the limit was already reached. Checking and incrementing happen in a
+/// single statement, so concurrent readers can never exceed the limit.
+///
+/// [`HitCounter`]: crate::web::HitCounter
+pub async fn claim_limited_hit(shortcode: &ShortCode, pool: &DatabasePool) -> Result<bool> {
+    let shortcode = shortcode.as_str();
+    Ok(sqlx::query!(
+        "UPDATE clips SET hits = hits + 1 WHERE shortcode = ? AND hits < max_hits",
+        shortcode
+    )
+    .execute(pool)
+    .await?
+    .rows_affected()
+        > 0)
+}
+
+pub async fn save_api_key<M: Into<model::NewApiKey>>(
+    api_key: ApiKey,
+    model: M,
+    secret: &KeySecret,
+    pool: &DatabasePool,
+) -> Result<ApiKey> {
+    let model = model.into();
+    let prefix = api_key.prefix();
+    let hash = api_key.hash(secret);
+    sqlx::query!(
+        "INSERT INTO api_keys (key_id, prefix, key_hash, name, created, expires, scopes)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+        model.key_id,
+        prefix,
+        hash,
+        model.name,
+        model.created,
+        model.expires,
+        model.scopes
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())?;
     Ok(api_key)
 }
 
+/// Find a stored API key by the prefix of the key, and compare the hashes of the keys with
+/// the same prefix in constant time.
+pub async fn get_api_key(
+    api_key: ApiKey,
+    secret: &KeySecret,
+    pool: &DatabasePool,
+) -> Result<model::ApiKey> {
+    use subtle::ConstantTimeEq;
+
+    let prefix = api_key.prefix();
+    let hash = api_key.hash(secret);
+    sqlx::query_as!(
+        model::ApiKey,
+        "SELECT key_id, key_hash, name, created, last_used, expires, scopes, revoked
+         FROM api_keys WHERE prefix = ?",
+        prefix
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .find(|stored| bool::from(stored.key_hash.ct_eq(&hash)))
+    .ok_or(DataError::Database(sqlx::Error::RowNotFound))
+}
+
+/// List every API key which hasn't been revoked, oldest first
+pub async fn list_api_keys(pool: &DatabasePool) -> Result<Vec<model::ApiKey>> {
+    Ok(sqlx::query_as!(
+        model::ApiKey,
+        "SELECT key_id, key_hash, name, created, last_used, expires, scopes, revoked
+         FROM api_keys WHERE revoked IS NULL ORDER BY created, key_id"
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
 pub enum RevocationStatus {
     Revoked,
     NotFound,
 }
 
-pub async fn revoke_api_key(api_key: ApiKey, pool: &DatabasePool) -> Result<RevocationStatus> {
-    let bytes = api_key.clone().into_inner();
-    Ok(
-        sqlx::query!("DELETE FROM api_keys WHERE api_key == ?", bytes)
-            .execute(pool)
-            .await
-            .map(|result| match result.rows_affected() {
-                0 => RevocationStatus::NotFound,
-                _ => RevocationStatus::Revoked,
-            })?,
+/// Revoke an API key. The key is kept, marked as revoked, so its clips keep their owner.
+///
+/// With a `key_id`, the key is only revoked if it has that id. Otherwise it counts as not found,
+/// just like a key which doesn't exist.
+pub async fn revoke_api_key(
+    api_key: ApiKey,
+    key_id: Option<&DbId>,
+    secret: &KeySecret,
+    pool: &DatabasePool,
+) -> Result<RevocationStatus> {
+    let hash = api_key.hash(secret);
+    let key_id = key_id.map(|key_id| key_id.to_string());
+    let now = chrono::Utc::now().timestamp();
+    Ok(sqlx::query!(
+        "UPDATE api_keys SET revoked = ?
+         WHERE key_hash = ? AND (? IS NULL OR key_id = ?) AND revoked IS NULL",
+        now,
+        hash,
+        key_id,
+        key_id
     )
+    .execute(pool)
+    .await
+    .map(|result| match result.rows_affected() {
+        0 => RevocationStatus::NotFound,
+        _ => RevocationStatus::Revoked,
+    })?)
 }
 
-pub async fn api_key_is_valid(api_key: ApiKey, pool: &DatabasePool) -> Result<bool> {
-    let bytes = api_key.clone().into_inner();
-    Ok(
-        sqlx::query("SELECT COUNT(api_key) FROM api_keys WHERE api_key = ?")
-            .bind(bytes)
-            .fetch_one(pool)
-            .await
-            .map(|row| {
-                let count: u32 = row.get(0);
-                count > 0
-            })?,
+/// Revoke an API key by its id, for when the key itself is not at hand
+pub async fn revoke_api_key_by_id(key_id: &DbId, pool: &DatabasePool) -> Result<RevocationStatus> {
+    let key_id = key_id.to_string();
+    let now = chrono::Utc::now().timestamp();
+    Ok(sqlx::query!(
+        "UPDATE api_keys SET revoked = ? WHERE key_id = ? AND revoked IS NULL",
+        now,
+        key_id
+    )
+    .execute(pool)
+    .await
+    .map(|result| match result.rows_affected() {
+        0 => RevocationStatus::NotFound,
+        _ => RevocationStatus::Revoked,
+    })?)
+}
+
+/// Count the stored clips, API keys and how the clips are configured
+pub async fn clip_stats(pool: &DatabasePool) -> Result<model::ClipStats> {
+    Ok(sqlx::query_as!(
+        model::ClipStats,
+        r#"SELECT COUNT(*) AS "clips!: i64",
+                  COALESCE(SUM(visibility = 'public'), 0) AS "public!: i64",
+                  COALESCE(SUM(visibility = 'unlisted'), 0) AS "unlisted!: i64",
+                  COALESCE(SUM(visibility = 'private'), 0) AS "private!: i64",
+                  COALESCE(SUM(password IS NOT NULL), 0) AS "protected!: i64",
+                  COALESCE(SUM(encrypted), 0) AS "encrypted!: i64",
+                  COALESCE(SUM(attachment IS NOT NULL), 0) AS "attachments!: i64",
+                  COALESCE(SUM(compressed_content IS NOT NULL), 0) AS "compressed!: i64",
+                  COALESCE(SUM(expires < strftime('%s', 'now')
+                               OR (max_hits IS NOT NULL AND hits >= max_hits)), 0)
+                      AS "expired!: i64",
+                  COALESCE(SUM(hits), 0) AS "hits!: i64",
+                  COALESCE(SUM(length(CAST(content AS BLOB)) + COALESCE(length(compressed_content), 0)
+                               + COALESCE(length(attachment), 0)), 0)
+                      AS "stored_bytes!: i64",
+                  (SELECT COUNT(*) FROM clip_revisions) AS "revisions!: i64",
+                  (SELECT COUNT(*) FROM api_keys WHERE revoked IS NULL) AS "api_keys!: i64"
+           FROM clips"#
     )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Replace the API keys which were stored before keys were hashed by their hash.
+///
+/// Returns the number of keys which were hashed. Without a secret the hashes would be no
+/// better than the keys themselves, so the keys are left alone and an error is returned.
+pub async fn hash_legacy_api_keys(secret: &KeySecret, pool: &DatabasePool) -> Result<u64> {
+    let mut transaction = pool.begin().await?;
+    let legacy = sqlx::query!("SELECT key_id, key_hash FROM api_keys WHERE prefix IS NULL")
+        .fetch_all(&mut transaction)
+        .await?;
+    if !legacy.is_empty() && secret.is_empty() {
+        return Err(DataError::MissingKeySecret);
+    }
+    for row in legacy.iter() {
+        // The column still holds the key itself
+        let api_key = ApiKey::from(row.key_hash.clone());
+        let prefix = api_key.prefix();
+        let hash = api_key.hash(secret);
+        sqlx::query!(
+            "UPDATE api_keys SET prefix = ?, key_hash = ? WHERE key_id = ?",
+            prefix,
+            hash,
+            row.key_id
+        )
+        .execute(&mut transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+    Ok(legacy.len() as u64)
+}
+
+/// Record that an API key was used just now.
+///
+/// The time is only written when it is more than a minute off, so a busy key doesn't turn
+/// every request into a write.
+pub async fn touch_api_key(key_id: &DbId, pool: &DatabasePool) -> Result<()> {
+    let key_id = key_id.to_string();
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query!(
+        "UPDATE api_keys SET last_used = ?
+         WHERE key_id = ? AND (last_used IS NULL OR last_used < ? - 60)",
+        now,
+        key_id,
+        now
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
 }
 
 pub async fn delete_expired(pool: &DatabasePool) -> Result<u64> {
-    Ok(
-        sqlx::query("DELETE FROM clips WHERE expires < strftime('%s', 'now')")
+    let mut transaction = pool.begin().await?;
+    let deleted = sqlx::query_as::<_, model::IndexedClip>(
+        r#"DELETE FROM clips WHERE
+                expires < strftime('%s', 'now')
+                OR (max_hits IS NOT NULL AND hits >= max_hits)
+           RETURNING rowid, title, content, compressed_content"#,
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+    unindex_clips(&deleted, &mut transaction).await?;
+    transaction.commit().await?;
+    Ok(deleted.len() as u64)
+}
+
+/// Compress one batch of clips which were stored before their content would have been
+/// compressed.
+///
+/// Clips are visited in `rowid` order after `after`. The returned `rowid` continues with the
+/// next batch, `None` means that every clip was visited.
+pub async fn compress_clips(
+    after: i64,
+    batch: i64,
+    compression: &Compression,
+    pool: &DatabasePool,
+) -> Result<Option<i64>> {
+    let threshold = i64::try_from(compression.threshold).unwrap_or(i64::MAX);
+    let rows = sqlx::query!(
+        r#"SELECT rowid AS "rowid!: i64", content FROM clips
+           WHERE rowid > ? AND compressed_content IS NULL AND length(CAST(content AS BLOB)) > ?
+           ORDER BY rowid LIMIT ?"#,
+        after,
+        threshold,
+        batch
+    )
+    .fetch_all(pool)
+    .await?;
+    let next = if rows.len() as i64 == batch {
+        rows.last().map(|row| row.rowid)
+    } else {
+        None
+    };
+    for row in rows {
+        if let Some(data) = compression.compress(&row.content) {
+            // Leave the clip alone when it got updated in the meantime, the search index
+            // holds the plain text either way
+            sqlx::query!(
+                "UPDATE clips SET content = '', compressed_content = ?
+                 WHERE rowid = ? AND compressed_content IS NULL AND content = ?",
+                data,
+                row.rowid,
+                row.content
+            )
             .execute(pool)
-            .await?
-            .rows_affected(),
+            .await?;
+        }
+    }
+    Ok(next)
+}
+
+/// Compress one batch of revisions, just like [`compress_clips`].
+pub async fn compress_revisions(
+    after: i64,
+    batch: i64,
+    compression: &Compression,
+    pool: &DatabasePool,
+) -> Result<Option<i64>> {
+    let threshold = i64::try_from(compression.threshold).unwrap_or(i64::MAX);
+    let rows = sqlx::query!(
+        r#"SELECT rowid AS "rowid!: i64", content FROM clip_revisions
+           WHERE rowid > ? AND compressed_content IS NULL AND length(CAST(content AS BLOB)) > ?
+           ORDER BY rowid LIMIT ?"#,
+        after,
+        threshold,
+        batch
     )
+    .fetch_all(pool)
+    .await?;
+    let next = if rows.len() as i64 == batch {
+        rows.last().map(|row| row.rowid)
+    } else {
+        None
+    };
+    for row in rows {
+        if let Some(data) = compression.compress(&row.content) {
+            // Revisions never change, so there is nothing to race with
+            sqlx::query!(
+                "UPDATE clip_revisions SET content = '', compressed_content = ?
+                 WHERE rowid = ?",
+                data,
+                row.rowid
+            )
+            .execute(pool)
+            .await?;
+        }
+    }
+    Ok(next)
 }
 
 #[cfg(test)]
@@ -154,6 +718,23 @@ pub mod test {
         }
     }
 
+    /// Insert a clip with the default shortcode format and compression
+    async fn insert_clip(
+        model: model::NewClip,
+        pool: &DatabasePool,
+    ) -> Result<model::Clip, DataError> {
+        use crate::data::compression::Compression;
+        use crate::domain::clip::field::ShortCodeFormat;
+
+        super::new_clip(
+            model,
+            &ShortCodeFormat::default(),
+            &Compression::default(),
+            pool,
+        )
+        .await
+    }
+
     fn model_new_clip(shortcode: &str) -> model::NewClip {
         use chrono::Utc;
 
@@ -161,10 +742,20 @@ pub mod test {
             clip_id: DbId::new().into(),
             content: format!("content for clip '{}'", shortcode),
             title: None,
-            shortcode: shortcode.into(),
+            shortcode: Some(shortcode.into()),
             posted: Utc::now().timestamp(),
             expires: None,
             password: None,
+            burn_after_reading: false,
+            max_hits: None,
+            owner: None,
+            manage_token: None,
+            language: None,
+            attachment: None,
+            mime_type: None,
+            filename: None,
+            encrypted: false,
+            visibility: "unlisted".to_owned(),
         }
     }
 
@@ -175,11 +766,291 @@ pub mod test {
         let pool = db.get_pool();
 
         let clip =
-            rt.block_on(async move { super::new_clip(model_new_clip("1"), &pool.clone()).await });
+            rt.block_on(async move { insert_clip(model_new_clip("1"), &pool.clone()).await });
         assert!(clip.is_ok());
 
         let clip = clip.unwrap();
         assert!(clip.shortcode == "1");
         assert!(clip.content == *"content for clip '1'");
     }
+
+    #[test]
+    fn test_shortcode_collision() {
+        use crate::data::compression::Compression;
+        use crate::domain::clip::field::ShortCodeFormat;
+
+        let rt = async_runtime();
+        let db = new_db(rt.handle());
+        let pool = db.get_pool();
+
+        rt.block_on(async move {
+            insert_clip(model_new_clip("taken"), pool).await.unwrap();
+            // A chosen shortcode is not replaced
+            assert!(matches!(
+                insert_clip(model_new_clip("taken"), pool).await,
+                Err(DataError::ShortCodeTaken(shortcode)) if shortcode == "taken"
+            ));
+            let generated = || model::NewClip {
+                shortcode: None,
+                ..model_new_clip("")
+            };
+            let clip = insert_clip(generated(), pool).await.unwrap();
+            assert!(ShortCodeFormat::default().matches(&clip.shortcode));
+
+            // Generating gives up once every shortcode of the format is taken
+            let format = ShortCodeFormat::new(4, "ab", false).unwrap();
+            for n in 0..16 {
+                let shortcode: String = (0..4)
+                    .map(|bit| if n >> bit & 1 == 0 { 'a' } else { 'b' })
+                    .collect();
+                insert_clip(model_new_clip(&shortcode), pool).await.unwrap();
+            }
+            assert!(matches!(
+                super::new_clip(generated(), &format, &Compression::default(), pool).await,
+                Err(DataError::ShortCodeTaken(shortcode)) if format.matches(&shortcode)
+            ));
+        });
+    }
+
+    #[test]
+    fn test_update_password() {
+        use crate::domain::clip::field::Password;
+        use crate::ShortCode;
+
+        let rt = async_runtime();
+        let db = new_db(rt.handle());
+        let pool = db.get_pool();
+
+        let clip = rt.block_on(async move {
+            insert_clip(model_new_clip("1"), pool).await.unwrap();
+            let password = Password::new("secret".to_owned());
+            super::update_password(&ShortCode::from("1"), password, pool)
+                .await
+                .unwrap();
+            super::get_clip(model_get_clip("1"), pool).await
+        });
+
+        assert_eq!(clip.unwrap().password, Some("secret".to_owned()));
+    }
+
+    #[test]
+    fn test_compressed_content() {
+        use crate::data::compression::Compression;
+        use crate::domain::clip::field::ShortCodeFormat;
+        use crate::domain::Clip;
+        use std::convert::TryFrom;
+
+        let rt = async_runtime();
+        let db = new_db(rt.handle());
+        let pool = db.get_pool();
+        let log = "INFO request served\n".repeat(compression::DEFAULT_THRESHOLD);
+
+        rt.block_on(async move {
+            let clip = insert_clip(
+                model::NewClip {
+                    content: log.clone(),
+                    ..model_new_clip("1")
+                },
+                pool,
+            )
+            .await
+            .unwrap();
+            assert!(clip.compressed_content.is_some());
+            assert!(clip.content.is_empty());
+            assert!(clip.compressed_content.as_ref().unwrap().len() < log.len());
+            let clip = Clip::try_from(clip).unwrap();
+            assert_eq!(clip.content.as_str(), log);
+
+            // Rows written before compression existed get compressed by the background job
+            let uncompressed = Compression::new(usize::MAX);
+            let model = model::NewClip {
+                content: log.clone(),
+                ..model_new_clip("2")
+            };
+            super::new_clip(model, &ShortCodeFormat::default(), &uncompressed, pool)
+                .await
+                .unwrap();
+            assert_eq!(
+                super::compress_clips(0, 100, &Compression::default(), pool)
+                    .await
+                    .unwrap(),
+                None
+            );
+            let clip = super::get_clip(model_get_clip("2"), pool).await.unwrap();
+            assert!(clip.compressed_content.is_some());
+            assert!(clip.content.is_empty());
+            let clip = Clip::try_from(clip).unwrap();
+            assert_eq!(clip.content.as_str(), log);
+        });
+    }
+
+    #[test]
+    fn test_search_compressed_clips() {
+        use crate::data::compression;
+
+        let rt = async_runtime();
+        let db = new_db(rt.handle());
+        let pool = db.get_pool();
+        let log = "INFO request served\n".repeat(compression::DEFAULT_THRESHOLD);
+
+        rt.block_on(async move {
+            let search = |query: &str| model::SearchClips {
+                query: query.to_owned(),
+                owner: String::new(),
+                now: chrono::Utc::now().timestamp(),
+                limit: 10,
+            };
+            for shortcode in ["1", "2", "3"] {
+                let model = model::NewClip {
+                    content: log.clone(),
+                    visibility: "public".to_owned(),
+                    ..model_new_clip(shortcode)
+                };
+                assert!(insert_clip(model, pool)
+                    .await
+                    .unwrap()
+                    .compressed_content
+                    .is_some());
+            }
+            let found = super::search_clips(search("served"), pool).await.unwrap();
+            assert_eq!(found.len(), 3);
+
+            // The index follows updates and deletes of compressed clips
+            let update = model::UpdateClip {
+                shortcode: "2".to_owned(),
+                content: "WARN disk almost full\n".repeat(compression::DEFAULT_THRESHOLD),
+                title: None,
+                expires: None,
+                password: None,
+            };
+            assert!(super::update_clip(update, &Default::default(), pool)
+                .await
+                .unwrap()
+                .compressed_content
+                .is_some());
+            super::delete_clip(&"3".into(), pool).await.unwrap();
+            let found = super::search_clips(search("served"), pool).await.unwrap();
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].shortcode, "1");
+            let found = super::search_clips(search("disk"), pool).await.unwrap();
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].shortcode, "2");
+
+            sqlx::query("INSERT INTO clips_fts (clips_fts) VALUES ('integrity-check')")
+                .execute(pool)
+                .await
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn test_hash_legacy_api_keys() {
+        use crate::web::api::{ApiKey, KeySecret};
+
+        let rt = async_runtime();
+        let db = new_db(rt.handle());
+        let pool = db.get_pool();
+
+        rt.block_on(async move {
+            let api_key = ApiKey::default();
+            let raw = api_key.clone().into_inner();
+            let key_id: String = DbId::new().into();
+            sqlx::query("INSERT INTO api_keys (key_id, key_hash) VALUES (?, ?)")
+                .bind(key_id)
+                .bind(raw.clone())
+                .execute(pool)
+                .await
+                .unwrap();
+            assert!(matches!(
+                super::hash_legacy_api_keys(&KeySecret::default(), pool).await,
+                Err(DataError::MissingKeySecret)
+            ));
+            let secret = KeySecret::new(b"secret");
+            assert!(super::get_api_key(api_key.clone(), &secret, pool)
+                .await
+                .is_err());
+
+            assert_eq!(super::hash_legacy_api_keys(&secret, pool).await.unwrap(), 1);
+            let stored = super::get_api_key(api_key, &secret, pool).await.unwrap();
+            assert_ne!(stored.key_hash, raw);
+            assert_eq!(super::hash_legacy_api_keys(&secret, pool).await.unwrap(), 0);
+        });
+    }
+
+    #[test]
+    fn test_clip_stats() {
+        let rt = async_runtime();
+        let db = new_db(rt.handle());
+        let pool = db.get_pool();
+
+        rt.block_on(async move {
+            insert_clip(model_new_clip("1"), pool).await.unwrap();
+            let public = model::NewClip {
+                visibility: "public".to_owned(),
+                password: Some("secret".to_owned()),
+                ..model_new_clip("2")
+            };
+            insert_clip(public, pool).await.unwrap();
+            let viewed = model::NewClip {
+                max_hits: Some(1),
+                ..model_new_clip("3")
+            };
+            insert_clip(viewed, pool).await.unwrap();
+            super::increase_hit_count(&"3".into(), 1, pool)
+                .await
+                .unwrap();
+
+            let stats = super::clip_stats(pool).await.unwrap();
+            assert_eq!(stats.clips, 3);
+            assert_eq!(stats.expired, 1);
+            assert_eq!(stats.public, 1);
+            assert_eq!(stats.unlisted, 2);
+            assert_eq!(stats.private, 0);
+            assert_eq!(stats.protected, 1);
+            assert_eq!(stats.api_keys, 0);
+            assert!(stats.stored_bytes > 0);
+        });
+    }
+
+    #[test]
+    fn test_list_and_revoke_api_keys_by_id() {
+        use crate::service::ask::NewApiKey;
+        use crate::web::api::ApiKey;
+
+        let rt = async_runtime();
+        let db = new_db(rt.handle());
+        let pool = db.get_pool();
+
+        rt.block_on(async move {
+            super::save_api_key(
+                ApiKey::default(),
+                NewApiKey::default(),
+                &Default::default(),
+                pool,
+            )
+            .await
+            .unwrap();
+            let keys = super::list_api_keys(pool).await.unwrap();
+            assert_eq!(keys.len(), 1);
+            let owned = model::NewClip {
+                owner: Some(keys[0].key_id.clone()),
+                ..model_new_clip("1")
+            };
+            insert_clip(owned, pool).await.unwrap();
+
+            let key_id: DbId = keys[0].key_id.parse().unwrap();
+            assert!(matches!(
+                super::revoke_api_key_by_id(&key_id, pool).await.unwrap(),
+                super::RevocationStatus::Revoked
+            ));
+            assert!(matches!(
+                super::revoke_api_key_by_id(&key_id, pool).await.unwrap(),
+                super::RevocationStatus::NotFound
+            ));
+            assert!(super::list_api_keys(pool).await.unwrap().is_empty());
+            // The clips of a revoked key keep their owner
+            let clip = super::get_clip(model_get_clip("1"), pool).await.unwrap();
+            assert_eq!(clip.owner, Some(key_id.to_string()));
+        });
+    }
 }
@@ -1,4 +1,7 @@
+pub mod api_key;
 pub mod clip;
+pub mod diff;
+pub mod encryption;
 pub mod maintenance;
 pub mod time;
 
@@ -0,0 +1,82 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data::DbId;
+use crate::Time;
+
+#[derive(Debug, Error)]
+#[error("invalid scope: {0}")]
+pub struct InvalidScope(pub String);
+
+/// What an API key may be used for.
+///
+/// Each API route states the scope it needs. Admin keys have every scope, and may also modify
+/// or read the clips of other keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Write,
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = InvalidScope;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            "admin" => Ok(Self::Admin),
+            other => Err(InvalidScope(other.to_owned())),
+        }
+    }
+}
+
+/// What is known about a stored API key.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiKeyInfo {
+    pub key_id: DbId,
+    pub name: String,
+    pub created: Time,
+    pub last_used: Option<Time>,
+    pub expires: Option<Time>,
+    pub scopes: Vec<Scope>,
+    #[serde(default)]
+    pub revoked: Option<Time>,
+}
+
+impl ApiKeyInfo {
+    pub fn is_admin(&self) -> bool {
+        self.scopes.contains(&Scope::Admin)
+    }
+
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.is_admin() || self.scopes.contains(&scope)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        match &self.expires {
+            Some(expires) => expires.timestamp() <= chrono::Utc::now().timestamp(),
+            None => false,
+        }
+    }
+}
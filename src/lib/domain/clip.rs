@@ -1,8 +1,11 @@
 pub mod field;
+pub mod limits;
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::Time;
+
 // Create custom Error
 #[derive(Debug, Error)]
 pub enum ClipError {
@@ -10,6 +13,8 @@ pub enum ClipError {
     InvalidPassword(String),
     #[error("invalid title: {0}")]
     InvalidTitle(String),
+    #[error("invalid max hits: {0}")]
+    InvalidMaxHits(String),
     #[error("empty content")]
     EmptyContent,
     #[error("invalid date: {0}")]
@@ -20,6 +25,32 @@ pub enum ClipError {
     Id(#[from] uuid::Error),
     #[error("hits parse error: {0}")]
     Hits(#[from] std::num::TryFromIntError),
+    #[error("password hash error: {0}")]
+    PasswordHash(#[from] argon2::password_hash::Error),
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(String),
+    #[error("invalid sort order: {0}")]
+    InvalidSort(String),
+    #[error("invalid search: {0}")]
+    InvalidSearch(String),
+    #[error("invalid language: {0}")]
+    InvalidLanguage(String),
+    #[error("invalid attachment: {0}")]
+    InvalidAttachment(String),
+    #[error("content is too large: {size} bytes, the limit is {limit} bytes")]
+    ContentTooLarge { size: usize, limit: usize },
+    #[error("title is too long, the limit is {0} characters")]
+    TitleTooLong(usize),
+    #[error("password is too long, the limit is {0} characters")]
+    PasswordTooLong(usize),
+    #[error("corrupt content: {0}")]
+    CorruptContent(String),
+    #[error("encryption error: {0}")]
+    Encryption(String),
+    #[error("invalid shortcode: {0}")]
+    InvalidShortCode(String),
+    #[error("invalid visibility: {0}")]
+    InvalidVisibility(String),
 }
 
 // Create custom data type for clips
@@ -32,6 +63,98 @@ pub struct Clip {
     pub title: field::Title,
     pub posted: field::Posted,
     pub expires: field::Expires,
+    // Only whether the clip has a password leaves the server, never the hash of it
+    #[serde(
+        rename = "protected",
+        serialize_with = "serialize_protected",
+        skip_deserializing
+    )]
     pub password: field::Password,
     pub hits: field::Hits,
+    pub max_hits: field::MaxHits,
+    pub burn_after_reading: field::BurnAfterReading,
+    pub language: field::Language,
+    pub encrypted: field::Encrypted,
+    pub visibility: field::Visibility,
+    // Binary payload of uploaded files, the content holds the file name then
+    pub attachment: Option<field::Attachment>,
+    // The owning API key is internal, just like the clip_id
+    #[serde(skip)]
+    pub owner: field::Owner,
+    #[serde(skip)]
+    pub manage_token: field::ManageToken,
+}
+
+fn serialize_protected<S: serde::Serializer>(
+    password: &field::Password,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bool(password.has_password())
+}
+
+/// Metadata of a [`Clip`] without its content, used when listing clips.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClipSummary {
+    pub shortcode: field::ShortCode,
+    pub title: field::Title,
+    pub posted: field::Posted,
+    pub expires: field::Expires,
+    pub hits: field::Hits,
+    pub protected: bool,
+    #[serde(default)]
+    pub visibility: field::Visibility,
+}
+
+impl From<Clip> for ClipSummary {
+    fn from(clip: Clip) -> Self {
+        Self {
+            protected: clip.password.has_password(),
+            shortcode: clip.shortcode,
+            title: clip.title,
+            posted: clip.posted,
+            expires: clip.expires,
+            hits: clip.hits,
+            visibility: clip.visibility,
+        }
+    }
+}
+
+/// One page of [`ClipSummary`], with the cursor to request the next page if there is one.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClipPage {
+    pub clips: Vec<ClipSummary>,
+    pub next_cursor: Option<String>,
+}
+
+/// Numbers about every stored [`Clip`], for the operators of the server.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClipStats {
+    pub clips: u64,
+    pub public: u64,
+    pub unlisted: u64,
+    pub private: u64,
+    // Password protected
+    pub protected: u64,
+    pub encrypted: u64,
+    pub attachments: u64,
+    pub compressed: u64,
+    // Expired or out of views, waiting to be deleted
+    pub expired: u64,
+    pub hits: u64,
+    // Size of the content and attachments as stored, after compression
+    pub stored_bytes: u64,
+    pub revisions: u64,
+    pub api_keys: u64,
+}
+
+/// A version of a [`Clip`] which got replaced by an update.
+///
+/// Revisions are numbered from 1, the current version of the clip is not a revision.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClipRevision {
+    pub revision: u32,
+    pub content: field::Content,
+    pub title: field::Title,
+    pub expires: field::Expires,
+    pub archived: Time,
 }
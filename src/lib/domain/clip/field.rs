@@ -3,15 +3,36 @@ mod clip_id;
 // export the field structure to field module so it can be direct access from outside
 pub use clip_id::ClipId;
 
+mod attachment;
+pub use attachment::Attachment;
+
+mod burn_after_reading;
+pub use burn_after_reading::BurnAfterReading;
+
 mod content;
 pub use content::Content;
 
+mod encrypted;
+pub use encrypted::Encrypted;
+
 mod expires;
 pub use expires::Expires;
 
 mod hits;
 pub use hits::Hits;
 
+mod language;
+pub use language::Language;
+
+mod manage_token;
+pub use manage_token::ManageToken;
+
+mod max_hits;
+pub use max_hits::MaxHits;
+
+mod owner;
+pub use owner::Owner;
+
 mod password;
 pub use password::Password;
 
@@ -19,7 +40,10 @@ mod posted;
 pub use posted::Posted;
 
 mod shortcode;
-pub use shortcode::ShortCode;
+pub use shortcode::{ShortCode, ShortCodeFormat};
 
 mod title;
 pub use title::Title;
+
+mod visibility;
+pub use visibility::Visibility;
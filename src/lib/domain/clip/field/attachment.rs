@@ -0,0 +1,146 @@
+use rocket::http::ContentType;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::clip::ClipError;
+
+/// Types which browsers display inline without running scripts, so they can be served as is.
+/// Everything else is served as a download.
+const INLINE_TYPES: [&str; 7] = [
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/avif",
+    "application/pdf",
+    "text/plain",
+];
+
+/// A binary payload of a clip, like a screenshot, a PDF or a tarball.
+///
+/// Only the metadata is serialized, the data itself is served by the raw route.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Attachment {
+    #[serde(skip)]
+    data: Vec<u8>,
+    mime_type: String,
+    filename: Option<String>,
+    size: usize,
+}
+
+impl Attachment {
+    pub const MAX_FILENAME_LENGTH: usize = 255;
+
+    /// Create an attachment from uploaded data, detecting its MIME type from the data itself
+    /// or, failing that, from the extension of the file name.
+    pub fn new(data: Vec<u8>, filename: Option<String>) -> Result<Self, ClipError> {
+        if data.is_empty() {
+            return Err(ClipError::EmptyContent);
+        }
+        let filename = filename.and_then(|filename| sanitize_filename(&filename));
+        let mime_type = infer::get(&data)
+            .map(|kind| kind.mime_type().to_owned())
+            .or_else(|| {
+                filename
+                    .as_deref()
+                    .and_then(|filename| filename.rsplit_once('.'))
+                    .and_then(|(_, extension)| ContentType::from_extension(extension))
+                    .map(|content_type| content_type.to_string())
+            })
+            .unwrap_or_else(|| match std::str::from_utf8(&data) {
+                Ok(_) => "text/plain".to_owned(),
+                Err(_) => "application/octet-stream".to_owned(),
+            });
+        Ok(Self::from_parts(data, mime_type, filename))
+    }
+
+    /// Restore a stored attachment, without detecting its type again.
+    pub fn from_parts(data: Vec<u8>, mime_type: String, filename: Option<String>) -> Self {
+        Self {
+            size: data.len(),
+            data,
+            mime_type,
+            filename,
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_parts(self) -> (Vec<u8>, String, Option<String>) {
+        (self.data, self.mime_type, self.filename)
+    }
+
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+
+    /// MIME type without parameters, like `text/plain` for `text/plain; charset=utf-8`
+    pub fn essence(&self) -> &str {
+        self.mime_type.split(';').next().unwrap_or_default().trim()
+    }
+
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// Name shown for the attachment, also used as the text content of the clip.
+    pub fn display_name(&self) -> &str {
+        self.filename().unwrap_or("upload")
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.is_inline() && self.mime_type.starts_with("image/")
+    }
+
+    /// Whether the attachment can safely be displayed by the browser instead of downloaded
+    pub fn is_inline(&self) -> bool {
+        INLINE_TYPES.contains(&self.essence())
+    }
+}
+
+/// Keep the last path component of an uploaded file name, without control or quote characters.
+fn sanitize_filename(filename: &str) -> Option<String> {
+    let filename: String = filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(Attachment::MAX_FILENAME_LENGTH)
+        .collect();
+    let filename = filename.trim();
+    if filename.is_empty() || filename == "." || filename == ".." {
+        None
+    } else {
+        Some(filename.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Attachment;
+
+    #[test]
+    fn test_detect_mime_type() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        let attachment = Attachment::new(png, Some("../../shot.png".to_owned())).unwrap();
+        assert_eq!(attachment.mime_type(), "image/png");
+        assert_eq!(attachment.filename(), Some("shot.png"));
+        assert!(attachment.is_image());
+
+        let html = b"<script>alert(1)</script>".to_vec();
+        let attachment = Attachment::new(html, Some("page.html".to_owned())).unwrap();
+        assert!(!attachment.is_inline());
+
+        let binary = vec![0, 159, 146, 150];
+        let attachment = Attachment::new(binary, None).unwrap();
+        assert_eq!(attachment.mime_type(), "application/octet-stream");
+        assert_eq!(attachment.display_name(), "upload");
+    }
+
+    #[test]
+    fn test_empty_attachment() {
+        assert!(Attachment::new(vec![], None).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use rocket::form::{self, FromFormField, ValueField};
 use serde::{Deserialize, Serialize};
 
+use crate::domain::clip::limits::Limits;
 use crate::domain::clip::ClipError;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -15,6 +16,12 @@ impl Content {
         }
     }
 
+    /// Restore stored content, which was checked against the limits in place when it was
+    /// saved. Lowering the limits later must not make existing clips unreadable.
+    pub(crate) fn from_stored(content: String) -> Self {
+        Self(content)
+    }
+
     pub fn into_inner(self) -> String {
         self.0
     }
@@ -22,6 +29,11 @@ impl Content {
     pub fn as_str(&self) -> &str {
         self.0.as_str()
     }
+
+    /// Make sure the content does not exceed the configured size limit
+    pub fn validate(&self, limits: &Limits) -> Result<(), ClipError> {
+        limits.check_content(self.0.len())
+    }
 }
 
 #[rocket::async_trait]
@@ -30,3 +42,22 @@ impl<'r> FromFormField<'r> for Content {
         Ok(Self::new(field.value).map_err(|e| form::Error::validation(format!("{}", e)))?)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::Content;
+    use crate::domain::clip::limits::Limits;
+    use crate::domain::clip::ClipError;
+
+    #[test]
+    fn test_content_limit() {
+        let limits = Limits::default();
+        let content = |size| Content::new(&"a".repeat(size)).unwrap();
+        assert!(content(limits.content).validate(&limits).is_ok());
+        assert!(matches!(
+            content(limits.content + 1).validate(&limits),
+            Err(ClipError::ContentTooLarge { .. })
+        ));
+        assert!(matches!(Content::new(" \n"), Err(ClipError::EmptyContent)));
+    }
+}
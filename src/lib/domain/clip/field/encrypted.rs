@@ -0,0 +1,29 @@
+use derive_more::Constructor;
+use rocket::form::{self, FromFormField, ValueField};
+use serde::{Deserialize, Serialize};
+
+/// Whether the content of a clip was encrypted by the client, with a key the server never sees
+#[derive(Clone, Constructor, Debug, Default, Deserialize, Serialize)]
+pub struct Encrypted(bool);
+
+impl Encrypted {
+    pub fn into_inner(self) -> bool {
+        self.0
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromFormField<'r> for Encrypted {
+    // An unchecked checkbox is not submitted at all
+    fn default() -> Option<Self> {
+        Some(Self(false))
+    }
+
+    fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
+        Ok(Self(bool::from_value(field)?))
+    }
+}
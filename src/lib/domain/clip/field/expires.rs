@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use chrono::{Duration, Utc};
 use rocket::form::{self, FromFormField, ValueField};
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +18,16 @@ impl Expires {
     pub fn into_inner(self) -> Option<Time> {
         self.0
     }
+
+    /// Make sure a user supplied expiry date is not already in the past
+    pub fn validate(&self) -> Result<(), ClipError> {
+        match &self.0 {
+            Some(time) if time.timestamp() <= Utc::now().timestamp() => Err(
+                ClipError::InvalidDate(format!("{} is in the past", time.clone().into_inner())),
+            ),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Default for Expires {
@@ -25,17 +36,37 @@ impl Default for Expires {
     }
 }
 
+/// Parse a duration relative to now, like `10m`, `3h`, `7d` or `2w`
+fn parse_duration(s: &str) -> Option<Result<Duration, ClipError>> {
+    let (idx, unit) = s.char_indices().last()?;
+    let amount = s[..idx].parse::<i64>().ok()?;
+    let duration = match unit {
+        'm' => Duration::try_minutes(amount),
+        'h' => Duration::try_hours(amount),
+        'd' => Duration::try_days(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => return None,
+    };
+    Some(duration.ok_or_else(|| ClipError::InvalidDate(format!("{} is out of range", s))))
+}
+
 impl FromStr for Expires {
     type Err = ClipError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
         if s.is_empty() {
-            Ok(Self(None))
-        } else {
-            match Time::from_str(s) {
-                Ok(time) => Ok(Self::new(time)),
-                Err(e) => Err(e.into()),
-            }
+            return Ok(Self(None));
         }
+        let time = match parse_duration(s) {
+            Some(duration) => Utc::now()
+                .checked_add_signed(duration?)
+                .map(Time::from)
+                .ok_or_else(|| ClipError::InvalidDate(format!("{} is out of range", s)))?,
+            None => Time::from_str(s)?,
+        };
+        let expires = Self::new(time);
+        expires.validate()?;
+        Ok(expires)
     }
 }
 
@@ -49,4 +80,53 @@ impl<'r> FromFormField<'r> for Expires {
                 .map_err(|e| form::Error::validation(format!("{}", e)))?)
         }
     }
+
+    fn default() -> Option<Self> {
+        Some(Self(None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use chrono::{Duration, Utc};
+
+    use super::Expires;
+    use crate::domain::clip::ClipError;
+
+    fn seconds_from_now(expires: Expires) -> i64 {
+        expires.into_inner().unwrap().timestamp() - Utc::now().timestamp()
+    }
+
+    #[test]
+    fn test_relative_durations() {
+        let minutes = seconds_from_now(Expires::from_str("10m").unwrap());
+        assert!((599..=600).contains(&minutes));
+        let hours = seconds_from_now(Expires::from_str("3h").unwrap());
+        assert!((3 * 3600 - 1..=3 * 3600).contains(&hours));
+        let days = seconds_from_now(Expires::from_str("7d").unwrap());
+        assert!((7 * 86400 - 1..=7 * 86400).contains(&days));
+    }
+
+    #[test]
+    fn test_rfc3339_with_offset() {
+        let time = (Utc::now() + Duration::days(1))
+            .with_timezone(&chrono::FixedOffset::east_opt(7200).unwrap());
+        let expires = Expires::from_str(&time.to_rfc3339()).unwrap();
+        assert_eq!(expires.into_inner().unwrap().timestamp(), time.timestamp());
+    }
+
+    #[test]
+    fn test_reject_past() {
+        assert!(matches!(
+            Expires::from_str("2001-01-01"),
+            Err(ClipError::InvalidDate(_))
+        ));
+        assert!(matches!(
+            Expires::from_str("-5m"),
+            Err(ClipError::InvalidDate(_))
+        ));
+        assert!(Expires::from_str("5x").is_err());
+    }
 }
@@ -0,0 +1,191 @@
+use std::str::FromStr;
+
+use rocket::form::{self, FromFormField, ValueField};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::clip::field::Content;
+use crate::domain::clip::ClipError;
+
+/// Language of the content of a clip, like `rust` or `sql`, used for syntax highlighting.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Language(Option<String>);
+
+impl Language {
+    pub const MAX_LENGTH: usize = 32;
+    pub const MARKDOWN: &'static str = "markdown";
+
+    pub fn new<T: Into<Option<String>>>(language: T) -> Result<Self, ClipError> {
+        let language: Option<String> = language.into();
+        match language.map(|language| language.trim().to_lowercase()) {
+            Some(language) if language.is_empty() => Ok(Self(None)),
+            Some(language) => {
+                let valid_chars = language
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+#-_.".contains(c));
+                if language == "md" {
+                    Ok(Self(Some(Self::MARKDOWN.to_owned())))
+                } else if valid_chars && language.len() <= Self::MAX_LENGTH {
+                    Ok(Self(Some(language)))
+                } else {
+                    Err(ClipError::InvalidLanguage(language))
+                }
+            }
+            None => Ok(Self(None)),
+        }
+    }
+
+    /// Guess the language from the content, for the languages which are pasted the most.
+    pub fn detect(content: &Content) -> Self {
+        let content = content.as_str().trim();
+        let first_line = content.lines().next().unwrap_or_default();
+
+        let language = if let Some(shebang) = first_line.strip_prefix("#!") {
+            // #!/bin/bash or #!/usr/bin/env python3
+            let mut words = shebang.split_whitespace();
+            let command = match words.next() {
+                Some(env) if env.ends_with("/env") => words.find(|word| !word.starts_with('-')),
+                command => command,
+            };
+            command
+                .and_then(|command| command.rsplit('/').next())
+                .map(|interpreter| interpreter.trim_end_matches(|c: char| c.is_ascii_digit()))
+                .map(|interpreter| match interpreter {
+                    "sh" | "zsh" => "bash",
+                    other => other,
+                })
+        } else if is_rust(content) {
+            Some("rust")
+        } else if is_sql(content) {
+            Some("sql")
+        } else if (content.starts_with('{') || content.starts_with('['))
+            && serde_json::from_str::<serde_json::Value>(content).is_ok()
+        {
+            Some("json")
+        } else if is_yaml(content) {
+            Some("yaml")
+        } else {
+            None
+        };
+        Self::new(language.map(str::to_owned)).unwrap_or_default()
+    }
+
+    pub fn into_inner(self) -> Option<String> {
+        self.0
+    }
+
+    pub fn as_deref(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Markdown clips are rendered as HTML instead of being highlighted
+    pub fn is_markdown(&self) -> bool {
+        self.as_deref() == Some(Self::MARKDOWN)
+    }
+}
+
+fn is_rust(content: &str) -> bool {
+    [
+        "fn ",
+        "impl ",
+        "pub struct ",
+        "use std::",
+        "#[derive(",
+        "let mut ",
+    ]
+    .iter()
+    .filter(|pattern| content.contains(*pattern))
+    .count()
+        >= 2
+}
+
+fn is_sql(content: &str) -> bool {
+    const STATEMENTS: [&str; 8] = [
+        "SELECT", "INSERT", "UPDATE", "DELETE", "CREATE", "ALTER", "DROP", "WITH",
+    ];
+    const CLAUSES: [&str; 6] = [" FROM ", " INTO ", " SET ", " TABLE ", " WHERE ", ";"];
+    let content = content.to_uppercase().replace('\n', " ");
+    let statement = content
+        .split_whitespace()
+        .next()
+        .map(|word| STATEMENTS.contains(&word))
+        .unwrap_or(false);
+    statement && CLAUSES.iter().any(|clause| content.contains(clause))
+}
+
+fn is_yaml(content: &str) -> bool {
+    if content.starts_with("---") {
+        return true;
+    }
+    let lines: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let mapping = |line: &&str| match line.trim_start_matches("- ").split_once(':') {
+        Some((key, value)) => {
+            !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "_-. ".contains(c))
+                && (value.is_empty() || value.starts_with(' '))
+        }
+        None => line.starts_with("- "),
+    };
+    lines.len() >= 2 && lines.iter().all(mapping)
+}
+
+impl FromStr for Language {
+    type Err = ClipError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s.to_string())
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromFormField<'r> for Language {
+    fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
+        Ok(Self::new(field.value.to_owned())
+            .map_err(|e| form::Error::validation(format!("{}", e)))?)
+    }
+
+    fn default() -> Option<Self> {
+        Some(Self(None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Language;
+    use crate::domain::clip::field::Content;
+
+    fn detect(content: &str) -> Option<String> {
+        Language::detect(&Content::new(content).unwrap()).into_inner()
+    }
+
+    #[test]
+    fn test_detect() {
+        let rust = "use std::fmt;\n\nfn main() {\n    let mut x = 1;\n}";
+        assert_eq!(detect(rust).as_deref(), Some("rust"));
+        let sql = "SELECT title, hits\nFROM clips\nWHERE hits > 10;";
+        assert_eq!(detect(sql).as_deref(), Some("sql"));
+        let yaml = "name: clishare\nservices:\n  - web\n  - db";
+        assert_eq!(detect(yaml).as_deref(), Some("yaml"));
+        assert_eq!(
+            detect("#!/usr/bin/env python3\nprint(1)").as_deref(),
+            Some("python")
+        );
+        assert_eq!(detect("just some notes"), None);
+    }
+
+    #[test]
+    fn test_invalid_language() {
+        assert!(Language::new("Rust".to_owned()).unwrap().is_set());
+        assert!(Language::new("".to_owned()).unwrap().as_deref().is_none());
+        assert!(Language::new("<script>".to_owned()).is_err());
+        assert!(Language::new("md".to_owned()).unwrap().is_markdown());
+    }
+}
@@ -0,0 +1,80 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rocket::form::{self, FromFormField, ValueField};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::clip::ClipError;
+
+/// Secret which lets the anonymous creator of a clip edit or delete it.
+///
+/// The plain token is only known when it's generated, the clip stores an argon2 hash of it.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ManageToken(Option<String>);
+
+impl ManageToken {
+    pub fn new<T: Into<Option<String>>>(token: T) -> Self {
+        Self(token.into().filter(|token| !token.trim().is_empty()))
+    }
+
+    /// Generate a new random token
+    pub fn generate() -> Self {
+        let bytes: Vec<u8> = (0..24).map(|_| rand::random::<u8>()).collect();
+        Self(Some(base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)))
+    }
+
+    pub fn into_inner(self) -> Option<String> {
+        self.0
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Hash the token so it can be stored
+    pub fn hash(self) -> Result<Self, ClipError> {
+        match self.0 {
+            Some(token) => {
+                let salt = SaltString::generate(&mut rand::thread_rng());
+                let hash = Argon2::default().hash_password(token.as_bytes(), &salt)?;
+                Ok(Self(Some(hash.to_string())))
+            }
+            None => Ok(Self(None)),
+        }
+    }
+
+    /// Check a plain token against this (hashed) token
+    pub fn verify(&self, candidate: &ManageToken) -> bool {
+        match (&self.0, &candidate.0) {
+            (Some(stored), Some(candidate)) => PasswordHash::new(stored)
+                .map(|hash| {
+                    Argon2::default()
+                        .verify_password(candidate.as_bytes(), &hash)
+                        .is_ok()
+                })
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromFormField<'r> for ManageToken {
+    fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
+        Ok(Self::new(field.value.trim().to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ManageToken;
+
+    #[test]
+    fn test_hash_and_verify() {
+        let token = ManageToken::generate();
+        let hashed = token.clone().hash().unwrap();
+        assert!(hashed.verify(&token));
+        assert!(!hashed.verify(&ManageToken::generate()));
+        assert!(!hashed.verify(&ManageToken::default()));
+        assert!(!ManageToken::default().verify(&token));
+    }
+}
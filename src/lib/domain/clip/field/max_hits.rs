@@ -0,0 +1,82 @@
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use rocket::form::{self, FromFormField, ValueField};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::clip::ClipError;
+
+/// Maximum number of times a clip can be viewed, unlimited when empty
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(try_from = "Option<u64>")]
+pub struct MaxHits(Option<u64>);
+
+impl MaxHits {
+    pub fn new<T: Into<Option<u64>>>(max_hits: T) -> Result<Self, ClipError> {
+        match max_hits.into() {
+            Some(0) => Err(ClipError::InvalidMaxHits(
+                "a clip must be viewable at least once".to_owned(),
+            )),
+            max_hits => Ok(Self(max_hits)),
+        }
+    }
+
+    pub fn into_inner(self) -> Option<u64> {
+        self.0
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl TryFrom<Option<u64>> for MaxHits {
+    type Error = ClipError;
+    fn try_from(max_hits: Option<u64>) -> Result<Self, Self::Error> {
+        Self::new(max_hits)
+    }
+}
+
+impl FromStr for MaxHits {
+    type Err = ClipError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            Ok(Self(None))
+        } else {
+            let max_hits = s
+                .trim()
+                .parse::<u64>()
+                .map_err(|e| ClipError::InvalidMaxHits(e.to_string()))?;
+            Self::new(max_hits)
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromFormField<'r> for MaxHits {
+    fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
+        Ok(Self::from_str(field.value).map_err(|e| form::Error::validation(format!("{}", e)))?)
+    }
+
+    fn default() -> Option<Self> {
+        Some(Self(None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MaxHits;
+
+    #[test]
+    fn test_zero_max_hits_rejected() {
+        assert!(MaxHits::new(0).is_err());
+        assert!(serde_json::from_str::<MaxHits>("0").is_err());
+        assert_eq!(
+            serde_json::from_str::<MaxHits>("3").unwrap().into_inner(),
+            Some(3)
+        );
+        assert!(!serde_json::from_str::<MaxHits>("null")
+            .unwrap()
+            .is_limited());
+    }
+}
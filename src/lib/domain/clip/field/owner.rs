@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use crate::data::DbId;
+
+/// Id of the API key which created a clip, clips created through the web page have no owner
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Owner(Option<DbId>);
+
+impl Owner {
+    pub fn new<T: Into<Option<DbId>>>(owner: T) -> Self {
+        Self(owner.into())
+    }
+
+    pub fn into_inner(self) -> Option<DbId> {
+        self.0
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn is_owned_by(&self, key_id: &DbId) -> bool {
+        self.0.as_ref() == Some(key_id)
+    }
+}
@@ -1,57 +1,215 @@
 use std::str::FromStr;
 
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
 use rocket::form::{self, FromFormField, ValueField};
 use serde::{Deserialize, Serialize};
 
+use crate::domain::clip::limits::Limits;
 use crate::domain::clip::ClipError;
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, PartialOrd)]
-pub struct Password(Option<String>);
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, PartialOrd)]
+#[serde(from = "Option<String>", into = "Option<String>")]
+pub struct Password {
+    password: Option<String>,
+    // Loaded from the database, where it is an argon2 hash or a legacy plaintext password.
+    // Whatever a user supplies is a password to be hashed, even if it looks like a hash.
+    stored: bool,
+}
 
 impl Password {
     // Into trait let us be able to accept either an Option or String as password
     // If passing String, it will be convert into Option<String>
-    pub fn new<T: Into<Option<String>>>(password: T) -> Result<Self, ClipError> {
-        //TODO: add password length validation
-        let password: Option<String> = password.into();
-        match password {
-            Some(password) => {
-                if !password.trim().is_empty() {
-                    Ok(Self(Some(password)))
-                } else {
-                    Ok(Self(None))
-                }
-            }
-            None => Ok(Self(None)),
+    // The length depends on the configured limits, see `Password::validate`
+    pub fn new<T: Into<Option<String>>>(password: T) -> Self {
+        Self::from(password.into())
+    }
+
+    /// Restore a stored password, usually a hash, without checking it against the limits.
+    pub(crate) fn from_stored(password: Option<String>) -> Self {
+        Self {
+            stored: true,
+            ..Self::from(password)
         }
     }
 
     pub fn into_inner(self) -> Option<String> {
-        self.0
+        self.password
+    }
+
+    /// Make sure a password supplied by the user does not exceed the configured length limit.
+    /// Stored passwords were checked before they were saved.
+    pub fn validate(&self, limits: &Limits) -> Result<(), ClipError> {
+        match &self.password {
+            Some(password) if !self.stored => limits.check_password(password),
+            _ => Ok(()),
+        }
     }
 
     pub fn has_password(&self) -> bool {
-        self.0.is_some()
+        self.password.is_some()
+    }
+
+    /// Hash the password with a random salt, producing a PHC string that is safe to store.
+    /// An empty password stays empty and a stored hash is returned unchanged.
+    pub fn hash(self) -> Result<Self, ClipError> {
+        match self.password {
+            Some(ref password) if !self.is_hashed() => {
+                let salt = SaltString::generate(&mut rand::thread_rng());
+                let hash = Argon2::default().hash_password(password.as_bytes(), &salt)?;
+                Ok(Self::from_stored(Some(hash.to_string())))
+            }
+            _ => Ok(self),
+        }
+    }
+
+    /// Digest of the stored password, which changes whenever the password does.
+    pub fn digest(&self) -> Option<String> {
+        use sha2::{Digest, Sha256};
+
+        self.password.as_ref().map(|password| {
+            Sha256::digest(password.as_bytes())
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect()
+        })
+    }
+
+    /// Whether the stored value is an argon2 hash rather than a legacy plaintext password
+    pub fn is_hashed(&self) -> bool {
+        match &self.password {
+            Some(password) if self.stored => PasswordHash::new(password)
+                .map(|hash| hash.algorithm.as_str().starts_with("argon2"))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Check a password supplied by the user against this (stored) password.
+    ///
+    /// Legacy plaintext passwords are compared in constant time so they can be
+    /// upgraded to a hash after a successful check.
+    pub fn verify(&self, candidate: &Password) -> bool {
+        use subtle::ConstantTimeEq;
+
+        match (&self.password, &candidate.password) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(stored), Some(candidate)) => {
+                if self.is_hashed() {
+                    PasswordHash::new(stored)
+                        .map(|hash| {
+                            Argon2::default()
+                                .verify_password(candidate.as_bytes(), &hash)
+                                .is_ok()
+                        })
+                        .unwrap_or(false)
+                } else {
+                    stored.as_bytes().ct_eq(candidate.as_bytes()).into()
+                }
+            }
+        }
     }
 }
 
-impl Default for Password {
-    fn default() -> Self {
-        Self(None)
+impl From<Option<String>> for Password {
+    fn from(password: Option<String>) -> Self {
+        Self {
+            password: password.filter(|password| !password.trim().is_empty()),
+            stored: false,
+        }
+    }
+}
+
+impl From<Password> for Option<String> {
+    fn from(password: Password) -> Self {
+        password.password
     }
 }
 
 impl FromStr for Password {
     type Err = ClipError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Self::new(s.to_string())
+        Ok(Self::new(s.to_string()))
     }
 }
 
 #[rocket::async_trait]
 impl<'r> FromFormField<'r> for Password {
     fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
-        Ok(Self::new(field.value.to_owned())
-            .map_err(|e| form::Error::validation(format!("{}", e)))?)
+        Ok(Self::new(field.value.to_owned()))
+    }
+
+    fn default() -> Option<Self> {
+        Some(Self::from(None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Password;
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hashed = Password::new("hunter2".to_owned()).hash().unwrap();
+        assert!(hashed.is_hashed());
+        assert_ne!(hashed.clone().into_inner().unwrap(), "hunter2");
+        assert!(hashed.verify(&Password::new("hunter2".to_owned())));
+        assert!(!hashed.verify(&Password::new("hunter3".to_owned())));
+        assert!(!hashed.verify(&Password::default()));
+    }
+
+    #[test]
+    fn test_verify_legacy_plaintext() {
+        let legacy = Password::from_stored(Some("hunter2".to_owned()));
+        assert!(!legacy.is_hashed());
+        assert!(legacy.verify(&Password::new("hunter2".to_owned())));
+        assert!(!legacy.verify(&Password::new("hunter".to_owned())));
+    }
+
+    #[test]
+    fn test_supplied_hash_is_hashed_again() {
+        let phc = Password::new("hunter2".to_owned())
+            .hash()
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        let supplied = Password::new(phc.clone());
+        assert!(!supplied.is_hashed());
+        let hashed = supplied.hash().unwrap();
+        assert_ne!(hashed.clone().into_inner().unwrap(), phc);
+        // The hash is only the password now, it doesn't unlock with the original one
+        assert!(hashed.verify(&Password::new(phc)));
+        assert!(!hashed.verify(&Password::new("hunter2".to_owned())));
+    }
+
+    #[test]
+    fn test_password_limit() {
+        use crate::domain::clip::limits::Limits;
+        use crate::domain::clip::ClipError;
+
+        let limits = Limits::default();
+        let password = |length| Password::new("p".repeat(length));
+        assert!(password(limits.password).validate(&limits).is_ok());
+        assert!(matches!(
+            password(limits.password + 1).validate(&limits),
+            Err(ClipError::PasswordTooLong(_))
+        ));
+        // Whatever looks like a hash is still checked when it comes from the user
+        let limits = Limits {
+            password: 16,
+            ..limits
+        };
+        let hash = password(4).hash().unwrap();
+        assert!(hash.validate(&limits).is_ok());
+        let supplied = Password::new(hash.into_inner());
+        assert!(supplied.validate(&limits).is_err());
+    }
+
+    #[test]
+    fn test_no_password() {
+        let none = Password::default().hash().unwrap();
+        assert!(!none.has_password());
+        assert!(none.verify(&Password::default()));
     }
 }
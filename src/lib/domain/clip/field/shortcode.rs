@@ -1,12 +1,107 @@
 use std::str::FromStr;
 
 use derive_more::From;
-use rocket::request::FromParam;
 use rocket::{UriDisplayPath, UriDisplayQuery};
 use serde::{Deserialize, Serialize};
 
 use crate::domain::clip::ClipError;
 
+/// How shortcodes of new clips are generated.
+///
+/// The format is configured once when the server starts. Shortcodes in links are checked
+/// against it, but links to clips generated with an earlier format keep working, as long as
+/// they look like a [custom shortcode](ShortCode::custom).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortCodeFormat {
+    length: usize,
+    alphabet: Vec<char>,
+}
+
+impl ShortCodeFormat {
+    pub const DEFAULT_LENGTH: usize = 10;
+    pub const DEFAULT_ALPHABET: &'static str = "abcdefghijklmnopqrstuvwxyz0123456789";
+    /// Characters which are easily mistaken for one another when a link is read out or typed
+    pub const LOOK_ALIKES: &'static str = "0oO1lIi";
+    pub const MIN_LENGTH: usize = 4;
+
+    /// Generate shortcodes of `length` characters, drawn from the ASCII letters and digits in
+    /// `alphabet`. Look-alike characters are left out when `exclude_look_alikes` is set.
+    pub fn new(
+        length: usize,
+        alphabet: &str,
+        exclude_look_alikes: bool,
+    ) -> Result<Self, ClipError> {
+        let mut chars: Vec<char> = Vec::with_capacity(alphabet.len());
+        for c in alphabet.chars() {
+            if !c.is_ascii_alphanumeric() {
+                return Err(ClipError::InvalidShortCode(format!(
+                    "the alphabet may only contain ASCII letters and digits, found {:?}",
+                    c
+                )));
+            }
+            let look_alike = exclude_look_alikes && Self::LOOK_ALIKES.contains(c);
+            if !look_alike && !chars.contains(&c) {
+                chars.push(c);
+            }
+        }
+        if chars.len() < 2 {
+            return Err(ClipError::InvalidShortCode(
+                "the alphabet needs at least two distinct characters".to_owned(),
+            ));
+        }
+        if !(Self::MIN_LENGTH..=ShortCode::MAX_CUSTOM_LENGTH).contains(&length) {
+            return Err(ClipError::InvalidShortCode(format!(
+                "the length has to be between {} and {}",
+                Self::MIN_LENGTH,
+                ShortCode::MAX_CUSTOM_LENGTH
+            )));
+        }
+        Ok(Self {
+            length,
+            alphabet: chars,
+        })
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn alphabet(&self) -> String {
+        self.alphabet.iter().collect()
+    }
+
+    pub fn generate(&self) -> ShortCode {
+        use rand::prelude::*;
+
+        let mut rng = thread_rng();
+        let shortcode = (0..self.length)
+            .map(|_| {
+                *self
+                    .alphabet
+                    .choose(&mut rng)
+                    .expect("sampling array should have values")
+            })
+            .collect();
+        ShortCode(shortcode)
+    }
+
+    /// Whether the shortcode could have been generated in this format, regardless of its length
+    pub fn matches(&self, shortcode: &str) -> bool {
+        !shortcode.is_empty()
+            && shortcode.chars().count() <= ShortCode::MAX_CUSTOM_LENGTH
+            && shortcode.chars().all(|c| self.alphabet.contains(&c))
+    }
+}
+
+impl Default for ShortCodeFormat {
+    fn default() -> Self {
+        Self {
+            length: Self::DEFAULT_LENGTH,
+            alphabet: Self::DEFAULT_ALPHABET.chars().collect(),
+        }
+    }
+}
+
 // derive_more From will automatically implement From trait to convert a String into ShortCode
 #[derive(
     Debug, Clone, Deserialize, Serialize, From, UriDisplayQuery, UriDisplayPath, Hash, Eq, PartialEq,
@@ -14,24 +109,62 @@ use crate::domain::clip::ClipError;
 pub struct ShortCode(String);
 
 impl ShortCode {
-    pub fn new() -> Self {
-        use rand::prelude::*;
-        let allowed_chars = [
-            'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q',
-            'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '1', '2', '3', '4', '5', '6', '7', '8',
-            '9', '0',
-        ];
+    /// Length bounds of a shortcode chosen by the creator of a clip
+    pub const MIN_CUSTOM_LENGTH: usize = 3;
+    pub const MAX_CUSTOM_LENGTH: usize = 64;
+    /// Words which can't be chosen as shortcode, since they show up in the routes
+    pub const RESERVED: [&'static str; 11] = [
+        "api", "clip", "delete", "edit", "history", "key", "raw", "recent", "search", "static",
+        "upload",
+    ];
 
-        let mut rng = thread_rng();
-        let mut shortcode = String::with_capacity(10);
-        for _ in 0..10 {
-            shortcode.push(
-                *allowed_chars
-                    .choose(&mut rng)
-                    .expect("sampling array should have values"),
-            )
+    /// Parse a shortcode from a link, which is either generated in `format` or chosen by the
+    /// creator of the clip.
+    pub fn parse(shortcode: &str, format: &ShortCodeFormat) -> Result<Self, ClipError> {
+        let shortcode = Self(shortcode.to_owned());
+        if !format.matches(shortcode.as_str()) {
+            shortcode.validate()?;
+        }
+        Ok(shortcode)
+    }
+
+    /// A shortcode chosen by the creator of a clip, like `deploy-notes`.
+    pub fn custom(shortcode: &str) -> Result<Self, ClipError> {
+        let shortcode = Self(shortcode.trim().to_owned());
+        shortcode.validate()?;
+        Ok(shortcode)
+    }
+
+    /// Make sure a shortcode chosen by the creator of a clip only uses lowercase letters, digits
+    /// and dashes, starts and ends with a letter or digit, and is not a reserved word.
+    pub fn validate(&self) -> Result<(), ClipError> {
+        let shortcode = self.as_str();
+        let length = shortcode.chars().count();
+        if !(Self::MIN_CUSTOM_LENGTH..=Self::MAX_CUSTOM_LENGTH).contains(&length) {
+            return Err(ClipError::InvalidShortCode(format!(
+                "{} has to be between {} and {} characters long",
+                shortcode,
+                Self::MIN_CUSTOM_LENGTH,
+                Self::MAX_CUSTOM_LENGTH
+            )));
+        }
+        let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+        if !shortcode.chars().all(|c| allowed(c) || c == '-')
+            || !shortcode.starts_with(allowed)
+            || !shortcode.ends_with(allowed)
+        {
+            return Err(ClipError::InvalidShortCode(format!(
+                "{} may only use lowercase letters, digits and dashes in between",
+                shortcode
+            )));
         }
-        Self(shortcode)
+        if Self::RESERVED.contains(&shortcode) {
+            return Err(ClipError::InvalidShortCode(format!(
+                "{} is reserved",
+                shortcode
+            )));
+        }
+        Ok(())
     }
 
     pub fn into_inner(self) -> String {
@@ -43,12 +176,6 @@ impl ShortCode {
     }
 }
 
-impl Default for ShortCode {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 // Since we are going to use ShortCode frequently within the web portion
 // so below implement some From for easily convertion between ShortCode and String
 impl From<ShortCode> for String {
@@ -63,21 +190,78 @@ impl From<&str> for ShortCode {
     }
 }
 
-// Turn data in URL into parameter
-impl<'r> FromParam<'r> for ShortCode {
-    type Error = &'r str;
-
-    fn from_param(param: &'r str) -> Result<Self, Self::Error> {
-        Ok(ShortCode::from(param))
-    }
-}
-
-// This one is done for completeness, won't be used later due to the complexity
-// introduce by the fact it returns Result instead of ShortCode as the From<&str> does
+// Command line arguments are parsed without knowing the format of the server, which checks
+// the shortcode once it gets the request
 impl FromStr for ShortCode {
     type Err = ClipError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // This .into() functionality is implemented above at From<&str>: &str -> ShortCode
-        Ok(Self(s.into()))
+        let shortcode = Self(s.to_owned());
+        let generated = !s.is_empty()
+            && s.len() <= Self::MAX_CUSTOM_LENGTH
+            && s.chars().all(|c| c.is_ascii_alphanumeric());
+        if !generated {
+            shortcode.validate()?;
+        }
+        Ok(shortcode)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ShortCode, ShortCodeFormat};
+
+    #[test]
+    fn test_custom_shortcode() {
+        assert_eq!(
+            ShortCode::custom(" deploy-notes ").unwrap().as_str(),
+            "deploy-notes"
+        );
+        assert!(ShortCode::custom("2024-q3").is_ok());
+        for invalid in [
+            "ab",
+            "Deploy",
+            "-notes",
+            "notes-",
+            "deploy notes",
+            "raw",
+            "api",
+        ] {
+            assert!(ShortCode::custom(invalid).is_err(), "{} is valid", invalid);
+        }
+        assert!(ShortCode::custom(&"a".repeat(ShortCode::MAX_CUSTOM_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_shortcode_format() {
+        let format = ShortCodeFormat::new(6, ShortCodeFormat::DEFAULT_ALPHABET, true).unwrap();
+        assert_eq!(format.alphabet().len(), 31);
+        for _ in 0..100 {
+            let shortcode = format.generate();
+            assert_eq!(shortcode.as_str().len(), 6);
+            assert!(!shortcode.as_str().contains(|c| "0o1li".contains(c)));
+            assert!(format.matches(shortcode.as_str()));
+        }
+        assert!(!format.matches("hell0"));
+        assert!(ShortCodeFormat::new(10, "ab-", false).is_err());
+        assert!(ShortCodeFormat::new(10, "0o1l", true).is_err());
+        assert!(ShortCodeFormat::new(2, "abc", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_shortcode() {
+        let format = ShortCodeFormat::default();
+        assert!(ShortCode::parse("abc123xyz0", &format).is_ok());
+        assert!(ShortCode::parse("deploy-notes", &format).is_ok());
+        // Outside of the alphabet, and no custom shortcode either
+        assert!(ShortCode::parse("ABC123XYZ0", &format).is_err());
+        let upper = ShortCodeFormat::new(10, "ABCDEFGHIJ", false).unwrap();
+        assert!(ShortCode::parse("ABCDEFGHIJ", &upper).is_ok());
+        for invalid in ["", "ABC/../x", "clip?x=1", "with space", "-dash"] {
+            assert!(
+                invalid.parse::<ShortCode>().is_err(),
+                "{} is valid",
+                invalid
+            );
+        }
     }
 }
@@ -3,34 +3,34 @@ use std::str::FromStr;
 use rocket::form::{self, FromFormField, ValueField};
 use serde::{Deserialize, Serialize};
 
+use crate::domain::clip::limits::Limits;
 use crate::domain::clip::ClipError;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Title(Option<String>);
 
 impl Title {
+    /// Create a title. Its length depends on the configured [`Limits`], so it is checked
+    /// with [`Title::validate`] when the clip is saved.
     pub fn new<T: Into<Option<String>>>(title: T) -> Self {
-        let title: Option<String> = title.into();
-        match title {
-            Some(title) => {
-                if !title.trim().is_empty() {
-                    Self(Some(title))
-                } else {
-                    Self(None)
-                }
-            }
-            None => Self(None),
-        }
+        Self::from_stored(title.into())
+    }
+
+    /// Restore a stored title, which was checked against the limits in place when it was saved.
+    pub(crate) fn from_stored(title: Option<String>) -> Self {
+        Self(title.filter(|title| !title.trim().is_empty()))
     }
 
     pub fn into_inner(self) -> Option<String> {
         self.0
     }
-}
 
-impl Default for Title {
-    fn default() -> Self {
-        Self::new(None)
+    /// Make sure the title does not exceed the configured length limit
+    pub fn validate(&self, limits: &Limits) -> Result<(), ClipError> {
+        match &self.0 {
+            Some(title) => limits.check_title(title),
+            None => Ok(()),
+        }
     }
 }
 
@@ -46,4 +46,8 @@ impl<'r> FromFormField<'r> for Title {
     fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
         Ok(Self::new(field.value.to_owned()))
     }
+
+    fn default() -> Option<Self> {
+        Some(Self(None))
+    }
 }
@@ -0,0 +1,86 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rocket::form::{self, FromFormField, ValueField};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::clip::ClipError;
+
+/// Who gets to see a clip.
+///
+/// Public clips are listed on the recent clips page, unlisted clips can be read by anyone who
+/// knows the link, and private clips only by the API key which created them.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    Public,
+    #[default]
+    Unlisted,
+    Private,
+}
+
+impl Visibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Unlisted => "unlisted",
+            Self::Private => "private",
+        }
+    }
+
+    pub fn is_public(&self) -> bool {
+        *self == Self::Public
+    }
+
+    pub fn is_private(&self) -> bool {
+        *self == Self::Private
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = ClipError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "public" => Ok(Self::Public),
+            "unlisted" => Ok(Self::Unlisted),
+            "private" => Ok(Self::Private),
+            other => Err(ClipError::InvalidVisibility(other.to_owned())),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromFormField<'r> for Visibility {
+    fn default() -> Option<Self> {
+        Some(Default::default())
+    }
+
+    fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
+        Ok(Self::from_str(field.value).map_err(|e| form::Error::validation(format!("{}", e)))?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Visibility;
+
+    #[test]
+    fn test_parse_visibility() {
+        assert_eq!("public".parse::<Visibility>().unwrap(), Visibility::Public);
+        assert_eq!(Visibility::default(), Visibility::Unlisted);
+        assert_eq!(
+            Visibility::Private
+                .to_string()
+                .parse::<Visibility>()
+                .unwrap(),
+            Visibility::Private
+        );
+        assert!("everyone".parse::<Visibility>().is_err());
+    }
+}
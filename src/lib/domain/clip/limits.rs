@@ -0,0 +1,90 @@
+use crate::domain::clip::ClipError;
+
+/// Size limits of the fields of a clip.
+///
+/// The limits are configured when the server starts and are handed to the service layer,
+/// which checks [`Content`](super::field::Content), [`Title`](super::field::Title) and
+/// [`Password`](super::field::Password) against them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Largest content, or uploaded file, in bytes
+    pub content: usize,
+    /// Longest title, in characters
+    pub title: usize,
+    /// Longest password, in characters
+    pub password: usize,
+}
+
+impl Limits {
+    pub const DEFAULT_CONTENT: usize = 4 * 1024 * 1024;
+    pub const DEFAULT_TITLE: usize = 200;
+    pub const DEFAULT_PASSWORD: usize = 128;
+
+    pub fn check_content(&self, size: usize) -> Result<(), ClipError> {
+        if size > self.content {
+            Err(ClipError::ContentTooLarge {
+                size,
+                limit: self.content,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn check_title(&self, title: &str) -> Result<(), ClipError> {
+        if title.chars().count() > self.title {
+            Err(ClipError::TitleTooLong(self.title))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn check_password(&self, password: &str) -> Result<(), ClipError> {
+        if password.chars().count() > self.password {
+            Err(ClipError::PasswordTooLong(self.password))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            content: Self::DEFAULT_CONTENT,
+            title: Self::DEFAULT_TITLE,
+            password: Self::DEFAULT_PASSWORD,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Limits;
+    use crate::domain::clip::ClipError;
+
+    #[test]
+    fn test_check_limits() {
+        let limits = Limits {
+            content: 8,
+            title: 3,
+            password: 4,
+        };
+        assert!(limits.check_content(8).is_ok());
+        assert!(matches!(
+            limits.check_content(9),
+            Err(ClipError::ContentTooLarge { size: 9, limit: 8 })
+        ));
+        // Titles and passwords are measured in characters rather than bytes
+        assert!(limits.check_title("äöü").is_ok());
+        assert!(matches!(
+            limits.check_title("four"),
+            Err(ClipError::TitleTooLong(3))
+        ));
+        assert!(limits.check_password("pass").is_ok());
+        assert!(matches!(
+            limits.check_password("hunter2"),
+            Err(ClipError::PasswordTooLong(4))
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+use serde::Serialize;
+
+/// Whether a line of a [`diff`] is in both texts or only in one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffLine {
+    pub change: Change,
+    pub text: String,
+}
+
+impl DiffLine {
+    fn new(change: Change, text: &str) -> Self {
+        Self {
+            change,
+            text: text.to_owned(),
+        }
+    }
+}
+
+/// Line based diff between two texts.
+///
+/// Uses the linear space variant of Myers' algorithm, so the memory needed grows with the
+/// number of lines instead of their product.
+pub fn diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let mut frontiers = Frontiers::new(old.len() + new.len());
+    conquer(&old, &new, &mut frontiers, &mut lines);
+    lines
+}
+
+/// Furthest reaching paths of the forward and backward searches, indexed by diagonal.
+struct Frontiers {
+    offset: isize,
+    forward: Vec<usize>,
+    backward: Vec<usize>,
+}
+
+impl Frontiers {
+    fn new(max_len: usize) -> Self {
+        let size = max_len + 3;
+        Self {
+            offset: size as isize,
+            forward: vec![0; 2 * size + 1],
+            backward: vec![0; 2 * size + 1],
+        }
+    }
+
+    fn index(&self, k: isize) -> usize {
+        (k + self.offset) as usize
+    }
+}
+
+fn common_prefix(old: &[&str], new: &[&str]) -> usize {
+    old.iter().zip(new).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix(old: &[&str], new: &[&str]) -> usize {
+    old.iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Diff two ranges of lines by splitting them at the middle of an edit script between them.
+fn conquer(old: &[&str], new: &[&str], frontiers: &mut Frontiers, lines: &mut Vec<DiffLine>) {
+    let prefix = common_prefix(old, new);
+    lines.extend(
+        old[..prefix]
+            .iter()
+            .map(|line| DiffLine::new(Change::Same, line)),
+    );
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = common_suffix(old, new);
+    let (old, new, same) = (
+        &old[..old.len() - suffix],
+        &new[..new.len() - suffix],
+        &old[old.len() - suffix..],
+    );
+
+    if old.is_empty() || new.is_empty() {
+        lines.extend(old.iter().map(|line| DiffLine::new(Change::Removed, line)));
+        lines.extend(new.iter().map(|line| DiffLine::new(Change::Added, line)));
+    } else {
+        let (x, y) = middle_snake(old, new, frontiers);
+        conquer(&old[..x], &new[..y], frontiers, lines);
+        conquer(&old[x..], &new[y..], frontiers, lines);
+    }
+    lines.extend(same.iter().map(|line| DiffLine::new(Change::Same, line)));
+}
+
+/// Find a point on a shortest edit script from `old` to `new` where the searches from both
+/// ends meet. Both ranges must be non-empty and differ in their first and last line.
+fn middle_snake(old: &[&str], new: &[&str], frontiers: &mut Frontiers) -> (usize, usize) {
+    let (n, m) = (old.len(), new.len());
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    let (forward, backward) = (frontiers.index(1), frontiers.index(1));
+    frontiers.forward[forward] = 0;
+    frontiers.backward[backward] = 0;
+
+    for d in 0..=(n + m).div_ceil(2) as isize {
+        for k in (-d..=d).rev().step_by(2) {
+            let (below, above) = (frontiers.index(k - 1), frontiers.index(k + 1));
+            let mut x =
+                if k == -d || (k != d && frontiers.forward[below] < frontiers.forward[above]) {
+                    frontiers.forward[above]
+                } else {
+                    frontiers.forward[below] + 1
+                };
+            let y = (x as isize - k) as usize;
+            let start = (x, y);
+            if x < n && y < m {
+                x += common_prefix(&old[x..], &new[y..]);
+            }
+            let index = frontiers.index(k);
+            frontiers.forward[index] = x;
+            if odd && (k - delta).abs() < d {
+                let opposite = frontiers.index(-(k - delta));
+                if x + frontiers.backward[opposite] >= n {
+                    return start;
+                }
+            }
+        }
+
+        for k in (-d..=d).rev().step_by(2) {
+            let (below, above) = (frontiers.index(k - 1), frontiers.index(k + 1));
+            let mut x =
+                if k == -d || (k != d && frontiers.backward[below] < frontiers.backward[above]) {
+                    frontiers.backward[above]
+                } else {
+                    frontiers.backward[below] + 1
+                };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let same = common_suffix(&old[..n - x], &new[..m - y]);
+                x += same;
+                y += same;
+            }
+            let index = frontiers.index(k);
+            frontiers.backward[index] = x;
+            if !odd && (k - delta).abs() <= d {
+                let opposite = frontiers.index(-(k - delta));
+                if x + frontiers.forward[opposite] >= n {
+                    return (n - x, m - y);
+                }
+            }
+        }
+    }
+    unreachable!("the searches from both ends always meet")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff, Change};
+
+    #[test]
+    fn test_diff_lines() {
+        let lines = diff("a\nb\nc", "a\nc\nd");
+        let changes: Vec<(Change, &str)> = lines
+            .iter()
+            .map(|line| (line.change, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (Change::Same, "a"),
+                (Change::Removed, "b"),
+                (Change::Same, "c"),
+                (Change::Added, "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        assert!(diff("a\nb", "a\nb")
+            .iter()
+            .all(|line| line.change == Change::Same));
+    }
+
+    #[test]
+    fn test_diff_is_minimal() {
+        let texts = [
+            "",
+            "a",
+            "a\nb\nc\na\nb\nb\na",
+            "c\nb\na\nb\na\nc",
+            "x\na\ny\nb\nz",
+            "b\nb\nb\nb",
+            "a\nb\nc\nd\ne\nf",
+            "f\ne\nd\nc\nb\na",
+        ];
+        for old in texts {
+            for new in texts {
+                let lines = diff(old, new);
+                let side = |change| {
+                    lines
+                        .iter()
+                        .filter(|line| line.change != change)
+                        .map(|line| line.text.as_str())
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(side(Change::Added), old.lines().collect::<Vec<_>>());
+                assert_eq!(side(Change::Removed), new.lines().collect::<Vec<_>>());
+                let same = lines.iter().filter(|l| l.change == Change::Same).count();
+                assert_eq!(same, longest_common(old, new), "{:?} -> {:?}", old, new);
+            }
+        }
+    }
+
+    fn longest_common(old: &str, new: &str) -> usize {
+        let old: Vec<&str> = old.lines().collect();
+        let new: Vec<&str> = new.lines().collect();
+        let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                common[i][j] = if old[i] == new[j] {
+                    common[i + 1][j + 1] + 1
+                } else {
+                    common[i + 1][j].max(common[i][j + 1])
+                };
+            }
+        }
+        common[0][0]
+    }
+}
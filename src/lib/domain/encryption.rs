@@ -0,0 +1,105 @@
+use std::fmt;
+use std::str::FromStr;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+
+use crate::domain::clip::ClipError;
+
+const NONCE_LENGTH: usize = 12;
+
+/// Key of an end-to-end encrypted clip, which never reaches the server.
+///
+/// Content is encrypted with AES-256-GCM and stored as the base64 encoded nonce followed by
+/// the ciphertext. The key is written in URL safe base64, as it ends up in the fragment of
+/// the link to the clip. `static/e2e.js` implements the same scheme for the web page.
+#[derive(Clone)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    pub fn generate() -> Self {
+        Self(rand::random())
+    }
+
+    pub fn encrypt(&self, content: &str) -> String {
+        let nonce: [u8; NONCE_LENGTH] = rand::random();
+        let ciphertext = self
+            .cipher()
+            .encrypt(Nonce::from_slice(&nonce), content.as_bytes())
+            .expect("failed to encrypt content");
+        base64::encode([nonce.as_slice(), ciphertext.as_slice()].concat())
+    }
+
+    pub fn decrypt(&self, encrypted: &str) -> Result<String, ClipError> {
+        let data =
+            base64::decode(encrypted.trim()).map_err(|e| ClipError::Encryption(e.to_string()))?;
+        if data.len() < NONCE_LENGTH {
+            return Err(ClipError::Encryption("ciphertext is too short".to_owned()));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+        let content = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| ClipError::Encryption("wrong key or damaged content".to_owned()))?;
+        String::from_utf8(content).map_err(|e| ClipError::Encryption(e.to_string()))
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&self.0.into())
+    }
+}
+
+impl fmt::Display for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            base64::encode_config(self.0, base64::URL_SAFE_NO_PAD)
+        )
+    }
+}
+
+// Keep the key out of logs
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EncryptionKey(..)")
+    }
+}
+
+impl FromStr for EncryptionKey {
+    type Err = ClipError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Accept a whole link to the clip as well as the bare key
+        let key = s.rsplit('#').next().unwrap_or(s);
+        base64::decode_config(key, base64::URL_SAFE_NO_PAD)
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .map(Self)
+            .ok_or_else(|| ClipError::Encryption("invalid key".to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::EncryptionKey;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = EncryptionKey::generate();
+        let encrypted = key.encrypt("top secret");
+        assert!(!encrypted.contains("secret"));
+        assert_eq!(key.decrypt(&encrypted).unwrap(), "top secret");
+        assert!(EncryptionKey::generate().decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_parse_key() {
+        let key = EncryptionKey::generate();
+        let link = format!("http://127.0.0.1:8000/clip/abc#{}", key);
+        let parsed = EncryptionKey::from_str(&link).unwrap();
+        assert_eq!(parsed.to_string(), key.to_string());
+        assert!(EncryptionKey::from_str("short").is_err());
+    }
+}
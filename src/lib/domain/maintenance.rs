@@ -2,13 +2,22 @@ use std::time::Duration;
 
 use tokio::runtime::Handle;
 
+use crate::data::compression::Compression;
 use crate::data::DatabasePool;
 use crate::service;
 
 pub struct Maintenance;
 
 impl Maintenance {
-    pub fn spawn(pool: DatabasePool, handle: Handle) -> Self {
+    pub fn spawn(pool: DatabasePool, compression: Compression, handle: Handle) -> Self {
+        // Content stored before it would have been compressed only needs one pass,
+        // everything written afterwards gets compressed right away
+        let compress_pool = pool.clone();
+        handle.spawn(async move {
+            if let Err(e) = service::action::compress_stored(&compression, &compress_pool).await {
+                eprintln!("failed to compress stored clips: {}", e)
+            }
+        });
         handle.spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(10));
             loop {
@@ -24,7 +24,11 @@ impl Time {
 impl FromStr for Time {
     type Err = chrono::ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // s should be in format like 2024-09-19
+        // s can be a full RFC 3339 timestamp with an offset like 2024-09-19T08:30:00+02:00
+        if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+            return Ok(time.with_timezone(&Utc).into());
+        }
+        // otherwise s should be in format like 2024-09-19
         match format!("{}T00:00:00Z", s).parse::<DateTime<Utc>>() {
             // derive_more From will convert DateTime<Utc> -> Time<DateTime<Utc>>
             Ok(time) => Ok(time.into()),
@@ -6,8 +6,13 @@ pub mod web;
 use rocket::fs::FileServer;
 use rocket::{Build, Rocket};
 
+use data::compression::Compression;
 use data::AppDatabase;
+use domain::clip::field::ShortCodeFormat;
+use domain::clip::limits::Limits;
 use domain::maintenance::Maintenance;
+use web::api::KeySecret;
+use web::highlight::Highlighter;
 use web::hit_counter::HitCounter;
 use web::renderer::Renderer;
 
@@ -22,17 +27,44 @@ pub use service::ServiceError;
 // Build the Rocket server
 pub struct RocketConfig {
     pub renderer: Renderer<'static>,
+    pub highlighter: Highlighter,
     pub database: AppDatabase,
     pub hit_counter: HitCounter,
     pub maintenance: Maintenance,
+    pub limits: Limits,
+    pub shortcode_format: ShortCodeFormat,
+    pub compression: Compression,
+    pub key_secret: KeySecret,
+}
+
+/// Rocket's limits on the size of request bodies, large enough for clips at the size limits.
+/// Form and JSON bodies get room for escaped content and the other fields of a clip.
+fn data_limits(limits: &Limits) -> rocket::data::Limits {
+    use rocket::data::ByteUnit;
+
+    const OVERHEAD: usize = 64 * 1024;
+    let escaped = ByteUnit::from(limits.content.saturating_mul(3).saturating_add(OVERHEAD));
+    let upload = ByteUnit::from(limits.content.saturating_add(OVERHEAD));
+    rocket::data::Limits::default()
+        .limit("form", escaped)
+        .limit("json", escaped)
+        .limit("data-form", upload)
+        .limit("file", upload)
 }
 
 pub fn rocket(config: RocketConfig) -> Rocket<Build> {
-    rocket::build()
+    let figment = rocket::Config::figment().merge(("limits", data_limits(&config.limits)));
+
+    rocket::custom(figment)
         .manage::<AppDatabase>(config.database)
         .manage::<Renderer>(config.renderer)
+        .manage::<Highlighter>(config.highlighter)
         .manage::<HitCounter>(config.hit_counter)
         .manage::<Maintenance>(config.maintenance)
+        .manage::<Limits>(config.limits)
+        .manage::<ShortCodeFormat>(config.shortcode_format)
+        .manage::<Compression>(config.compression)
+        .manage::<KeySecret>(config.key_secret)
         .mount("/", web::http::routes())
         .mount("/api/clip", web::api::routes())
         .mount("/static", FileServer::from("static"))
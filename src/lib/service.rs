@@ -13,6 +13,8 @@ pub enum ServiceError {
     NotFound,
     #[error("permissions not met: {0}")]
     PermissionError(String),
+    #[error("conflict: {0}")]
+    Conflict(String),
 }
 
 impl From<DataError> for ServiceError {
@@ -22,6 +24,9 @@ impl From<DataError> for ServiceError {
                 sqlx::Error::RowNotFound => Self::NotFound,
                 other => Self::Data(DataError::Database(other)),
             },
+            e @ DataError::ShortCodeTaken(_) => Self::Conflict(e.to_string()),
+            DataError::Clip(e) => Self::Clip(e),
+            other => Self::Data(other),
         }
     }
 }
@@ -1,33 +1,323 @@
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 
-use sqlx::Acquire;
-
-use crate::data::{query, DatabasePool, Transaction};
+use crate::data::compression::Compression;
+use crate::data::{query, DataError, DatabasePool, DbId, Transaction};
+use crate::domain::api_key::{ApiKeyInfo, Scope};
+use crate::domain::clip::field::{self, Hits, Password, ShortCodeFormat};
+use crate::domain::clip::limits::Limits;
+use crate::domain::clip::{ClipPage, ClipRevision, ClipStats, ClipSummary};
 use crate::service::ask;
-use crate::web::api::ApiKey;
-use crate::{Clip, ServiceError, ShortCode};
+use crate::web::api::{ApiKey, KeySecret};
+use crate::{Clip, ClipError, ServiceError, ShortCode};
+
+/// Run CPU heavy work like password hashing on the blocking thread pool, so it doesn't
+/// stall the other requests served by the async workers.
+async fn blocking<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
 
 pub async fn get_clip(req: ask::GetClip, pool: &DatabasePool) -> Result<Clip, ServiceError> {
     let user_password = req.password.clone();
+    let unlocked = req.unlock.clone();
+    let reader = req.reader.clone();
     // convert ask::GetClip -> model::GetClip -> domain::Clip
-    let clip: Clip = query::get_clip(req, pool).await?.try_into()?;
-    if clip.password.has_password() {
-        if clip.password == user_password {
-            Ok(clip)
+    let mut clip: Clip = query::get_clip(req, pool).await?.try_into()?;
+    check_visibility(&clip, reader.as_ref())?;
+    check_password(&mut clip, &user_password, unlocked.as_ref(), pool).await?;
+    // Hits of clips with a view limit are counted right away instead of being buffered,
+    // otherwise the limit could be exceeded until the buffer gets committed
+    if clip.max_hits.is_limited() {
+        if !query::claim_limited_hit(&clip.shortcode, pool).await? {
+            return Err(ServiceError::NotFound);
+        }
+        clip.hits = Hits::new(clip.hits.into_inner() + 1);
+    }
+    // Only the reader which actually deletes the clip gets to see it
+    if clip.burn_after_reading.is_set() && query::delete_clip(&clip.shortcode, pool).await? == 0 {
+        return Err(ServiceError::NotFound);
+    }
+    Ok(clip)
+}
+
+/// Make sure only the owner of a private clip, or an admin, gets to read it. Everybody else
+/// is told the clip doesn't exist.
+fn check_visibility(clip: &Clip, reader: Option<&ApiKeyInfo>) -> Result<(), ServiceError> {
+    if !clip.visibility.is_private() {
+        return Ok(());
+    }
+    let authorized = match reader {
+        Some(reader) => reader.is_admin() || clip.owner.is_owned_by(&reader.key_id),
+        None => false,
+    };
+    if authorized {
+        Ok(())
+    } else {
+        Err(ServiceError::NotFound)
+    }
+}
+
+/// Make sure the reader of a password protected clip knows the password or unlocked it before.
+async fn check_password(
+    clip: &mut Clip,
+    password: &Password,
+    unlocked: Option<&ask::Unlocked>,
+    pool: &DatabasePool,
+) -> Result<(), ServiceError> {
+    let unlocked = unlocked
+        .map(|claim| {
+            claim.clip_id == clip.clip_id.clone().into_inner()
+                && Some(&claim.password_digest) == clip.password.digest().as_ref()
+        })
+        .unwrap_or(false);
+    if clip.password.has_password() && !unlocked {
+        let (stored, password) = (clip.password.clone(), password.clone());
+        if blocking(move || stored.verify(&password)).await {
+            // Rows written before passwords were hashed still hold plaintext,
+            // upgrade them the first time they are checked successfully
+            if !clip.password.is_hashed() {
+                let legacy = clip.password.clone();
+                clip.password = blocking(move || legacy.hash()).await?;
+                query::update_password(&clip.shortcode, clip.password.clone(), pool).await?;
+            }
         } else {
-            Err(ServiceError::PermissionError("Invalid password".to_owned()))
+            return Err(ServiceError::PermissionError("Invalid password".to_owned()));
         }
+    }
+    Ok(())
+}
+
+/// Retrieve a clip together with its revisions, on behalf of whoever may modify it.
+pub async fn get_clip_history(
+    shortcode: &ShortCode,
+    credential: ask::Credential,
+    pool: &DatabasePool,
+) -> Result<(Clip, Vec<ClipRevision>), ServiceError> {
+    let clip = get_owned_clip(shortcode, credential, pool).await?;
+    let revisions = query::list_revisions(&clip.shortcode, pool)
+        .await?
+        .into_iter()
+        .map(ClipRevision::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((clip, revisions))
+}
+
+/// List the revisions of a clip on behalf of whoever may modify it.
+pub async fn list_revisions(
+    shortcode: &ShortCode,
+    credential: ask::Credential,
+    pool: &DatabasePool,
+) -> Result<Vec<ClipRevision>, ServiceError> {
+    get_owned_clip(shortcode, credential, pool).await?;
+    Ok(query::list_revisions(shortcode, pool)
+        .await?
+        .into_iter()
+        .map(ClipRevision::try_from)
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Retrieve a single revision of a clip on behalf of whoever may modify it.
+pub async fn get_revision(
+    shortcode: &ShortCode,
+    revision: u32,
+    credential: ask::Credential,
+    pool: &DatabasePool,
+) -> Result<ClipRevision, ServiceError> {
+    get_owned_clip(shortcode, credential, pool).await?;
+    Ok(query::get_revision(shortcode, revision, pool)
+        .await?
+        .try_into()?)
+}
+
+/// Check the fields of a request against the configured size limits.
+fn validate(
+    content: &field::Content,
+    title: &field::Title,
+    password: &Password,
+    limits: &Limits,
+) -> Result<(), ClipError> {
+    content.validate(limits)?;
+    title.validate(limits)?;
+    password.validate(limits)
+}
+
+pub async fn new_clip(
+    req: ask::NewClip,
+    limits: &Limits,
+    format: &ShortCodeFormat,
+    compression: &Compression,
+    pool: &DatabasePool,
+) -> Result<Clip, ServiceError> {
+    validate(&req.content, &req.title, &req.password, limits)?;
+    if let Some(attachment) = &req.attachment {
+        limits.check_content(attachment.data().len())?;
+    }
+    req.expires.validate()?;
+    if let Some(shortcode) = &req.shortcode {
+        shortcode.validate()?;
+    }
+    if req.visibility.is_private() && !req.owner.is_set() {
+        return Err(ClipError::InvalidVisibility(
+            "private clips need an API key to be read with".to_owned(),
+        )
+        .into());
+    }
+    // Ciphertext gives nothing away about the language
+    let language = if req.language.is_set() || req.encrypted.is_set() {
+        req.language
     } else {
+        field::Language::detect(&req.content)
+    };
+    let (password, manage_token) = (req.password.clone(), req.manage_token.clone());
+    let req = ask::NewClip {
+        password: blocking(move || password.hash()).await?,
+        language,
+        manage_token: blocking(move || manage_token.hash()).await?,
+        ..req
+    };
+    Ok(query::new_clip(req, format, compression, pool)
+        .await?
+        .try_into()?)
+}
+
+/// List one page of the clips owned by an API key, without their content.
+pub async fn list_clips(
+    req: ask::ListClips,
+    pool: &DatabasePool,
+) -> Result<ClipPage, ServiceError> {
+    let limit = req.limit as usize;
+    let mut clips = query::list_clips(req, pool).await?;
+
+    let next_cursor = if clips.len() > limit {
+        clips.truncate(limit);
+        clips
+            .last()
+            .map(|clip| clip.cursor())
+            .transpose()?
+            .map(|cursor| cursor.to_string())
+    } else {
+        None
+    };
+    Ok(ClipPage {
+        clips: clips
+            .into_iter()
+            .map(ClipSummary::try_from)
+            .collect::<Result<Vec<_>, _>>()?,
+        next_cursor,
+    })
+}
+
+/// List the most recently posted public clips, returning their metadata only.
+pub async fn recent_clips(
+    limit: u32,
+    pool: &DatabasePool,
+) -> Result<Vec<ClipSummary>, ServiceError> {
+    query::recent_clips(limit, pool)
+        .await?
+        .into_iter()
+        .map(|clip| Ok(ClipSummary::try_from(clip)?))
+        .collect()
+}
+
+/// Search the title and content of clips, returning their metadata only.
+///
+/// Every word of the query is matched as a plain prefix, FTS5 query syntax and punctuation
+/// are taken literally.
+pub async fn search_clips(
+    req: ask::SearchClips,
+    pool: &DatabasePool,
+) -> Result<Vec<ClipSummary>, ServiceError> {
+    if req.query.trim().is_empty() {
+        return Err(ClipError::InvalidSearch("empty search".to_owned()).into());
+    }
+    let query = req
+        .query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let req = ask::SearchClips { query, ..req };
+    match query::search_clips(req, pool).await {
+        Ok(clips) => clips
+            .into_iter()
+            .map(|clip| Ok(ClipSummary::try_from(clip)?))
+            .collect(),
+        Err(DataError::Database(sqlx::Error::Database(e))) if e.message().contains("fts5") => {
+            Err(ClipError::InvalidSearch(e.message().to_owned()).into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Retrieve a clip on behalf of whoever may modify it.
+///
+/// API keys have to be the key which created the clip or an admin key, otherwise the
+/// management token issued when the clip was created on the web page has to match. The clip
+/// password is not needed, and the read is neither counted nor does it burn the clip.
+pub async fn get_owned_clip(
+    shortcode: &ShortCode,
+    credential: ask::Credential,
+    pool: &DatabasePool,
+) -> Result<Clip, ServiceError> {
+    let clip: Clip = query::get_clip(shortcode.clone(), pool).await?.try_into()?;
+    let authorized = match credential {
+        ask::Credential::ApiKey(api_key) => {
+            api_key.is_admin() || clip.owner.is_owned_by(&api_key.key_id)
+        }
+        ask::Credential::ManageToken(token) => {
+            let manage_token = clip.manage_token.clone();
+            blocking(move || manage_token.verify(&token)).await
+        }
+    };
+    if authorized {
         Ok(clip)
+    } else {
+        Err(ServiceError::PermissionError(
+            "only the owner of the clip can modify it".to_owned(),
+        ))
     }
 }
 
-pub async fn new_clip(req: ask::NewClip, pool: &DatabasePool) -> Result<Clip, ServiceError> {
-    Ok(query::new_clip(req, pool).await?.try_into()?)
+pub async fn update_clip(
+    req: ask::UpdateClip,
+    credential: ask::Credential,
+    limits: &Limits,
+    compression: &Compression,
+    pool: &DatabasePool,
+) -> Result<Clip, ServiceError> {
+    let clip = get_owned_clip(&req.shortcode, credential, pool).await?;
+    // The key never reaches the server, anything saved here would replace the ciphertext
+    if clip.encrypted.is_set() {
+        return Err(ClipError::Encryption("encrypted clips can't be edited".to_owned()).into());
+    }
+    validate(&req.content, &req.title, &req.password, limits)?;
+    req.expires.validate()?;
+    let password = req.password.clone();
+    let req = ask::UpdateClip {
+        password: blocking(move || password.hash()).await?,
+        ..req
+    };
+    Ok(query::update_clip(req, compression, pool)
+        .await?
+        .try_into()?)
 }
 
-pub async fn update_clip(req: ask::UpdateClip, pool: &DatabasePool) -> Result<Clip, ServiceError> {
-    Ok(query::update_clip(req, pool).await?.try_into()?)
+pub async fn delete_clip(
+    req: ask::DeleteClip,
+    credential: ask::Credential,
+    pool: &DatabasePool,
+) -> Result<(), ServiceError> {
+    get_owned_clip(&req.shortcode, credential, pool).await?;
+    match query::delete_clip(&req.shortcode, pool).await? {
+        0 => Err(ServiceError::NotFound),
+        _ => Ok(()),
+    }
 }
 
 pub async fn begin_transaction(pool: &DatabasePool) -> Result<Transaction<'_>, ServiceError> {
@@ -46,23 +336,108 @@ pub async fn increase_hit_count(
     Ok(query::increase_hit_count(shortcode, hits, pool).await?)
 }
 
-pub async fn generate_api_key(pool: &DatabasePool) -> Result<ApiKey, ServiceError> {
+/// Generate an [`ApiKey`] with the name, scopes and expiry of the request
+pub async fn create_api_key(
+    req: ask::NewApiKey,
+    secret: &KeySecret,
+    pool: &DatabasePool,
+) -> Result<ApiKey, ServiceError> {
     let api_key = ApiKey::default();
-    Ok(query::save_api_key(api_key, pool).await?)
+    Ok(query::save_api_key(api_key, req, secret, pool).await?)
+}
+
+/// Generate an unnamed [`ApiKey`] which may read and write its own clips
+pub async fn generate_api_key(
+    secret: &KeySecret,
+    pool: &DatabasePool,
+) -> Result<ApiKey, ServiceError> {
+    create_api_key(ask::NewApiKey::default(), secret, pool).await
+}
+
+/// Generate an [`ApiKey`] which may modify every clip
+pub async fn generate_admin_api_key(
+    secret: &KeySecret,
+    pool: &DatabasePool,
+) -> Result<ApiKey, ServiceError> {
+    let req = ask::NewApiKey {
+        scopes: vec![Scope::Read, Scope::Write, Scope::Admin],
+        ..Default::default()
+    };
+    create_api_key(req, secret, pool).await
+}
+
+pub async fn get_api_key_info(
+    api_key: ApiKey,
+    secret: &KeySecret,
+    pool: &DatabasePool,
+) -> Result<ApiKeyInfo, ServiceError> {
+    Ok(query::get_api_key(api_key, secret, pool)
+        .await?
+        .try_into()?)
 }
 
+/// Revoke an API key, or with a `key_id`, only if it is the key with that id
 pub async fn revoke_api_key(
     api_key: ApiKey,
+    key_id: Option<&DbId>,
+    secret: &KeySecret,
+    pool: &DatabasePool,
+) -> Result<query::RevocationStatus, ServiceError> {
+    Ok(query::revoke_api_key(api_key, key_id, secret, pool).await?)
+}
+
+/// List every stored API key, without the keys themselves
+pub async fn list_api_keys(pool: &DatabasePool) -> Result<Vec<ApiKeyInfo>, ServiceError> {
+    query::list_api_keys(pool)
+        .await?
+        .into_iter()
+        .map(|api_key| Ok(api_key.try_into()?))
+        .collect()
+}
+
+pub async fn revoke_api_key_by_id(
+    key_id: &DbId,
     pool: &DatabasePool,
 ) -> Result<query::RevocationStatus, ServiceError> {
-    Ok(query::revoke_api_key(api_key, pool).await?)
+    Ok(query::revoke_api_key_by_id(key_id, pool).await?)
 }
 
-pub async fn api_key_is_valid(api_key: ApiKey, pool: &DatabasePool) -> Result<bool, ServiceError> {
-    Ok(query::api_key_is_valid(api_key, pool).await?)
+/// Delete a clip no matter who owns it, for operators dealing with abuse
+pub async fn purge_clip(shortcode: &ShortCode, pool: &DatabasePool) -> Result<(), ServiceError> {
+    match query::delete_clip(shortcode, pool).await? {
+        0 => Err(ServiceError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+pub async fn clip_stats(pool: &DatabasePool) -> Result<ClipStats, ServiceError> {
+    Ok(query::clip_stats(pool).await?.try_into()?)
+}
+
+/// Record that an API key was used for a request
+pub async fn touch_api_key(api_key: &ApiKeyInfo, pool: &DatabasePool) -> Result<(), ServiceError> {
+    Ok(query::touch_api_key(&api_key.key_id, pool).await?)
 }
 
 pub async fn delete_expires(pool: &DatabasePool) -> Result<u64, ServiceError> {
     println!("Start deleting");
     Ok(query::delete_expired(pool).await?)
 }
+
+/// Compress the content of clips and revisions which were stored uncompressed, in batches.
+pub async fn compress_stored(
+    compression: &Compression,
+    pool: &DatabasePool,
+) -> Result<(), ServiceError> {
+    const BATCH: i64 = 100;
+
+    let mut after = Some(0);
+    while let Some(rowid) = after {
+        after = query::compress_clips(rowid, BATCH, compression, pool).await?;
+    }
+    let mut after = Some(0);
+    while let Some(rowid) = after {
+        after = query::compress_revisions(rowid, BATCH, compression, pool).await?;
+    }
+    Ok(())
+}
@@ -1,14 +1,35 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[allow(unused_imports)]
 use derive_more::Constructor;
 use serde::{Deserialize, Serialize};
 
+use crate::data::DbId;
+use crate::domain::api_key::{ApiKeyInfo, Scope};
 use crate::domain::clip::field;
-use crate::ShortCode;
+use crate::{ClipError, ShortCode};
 
 /// Structure to request from the database taht we want to retrieve a clip
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GetClip {
     pub shortcode: ShortCode,
     pub password: field::Password,
+    // Entering the password before grants access to a password protected clip without it
+    #[serde(skip)]
+    pub unlock: Option<Unlocked>,
+    // Private clips are only found for the API key which created them, or an admin key
+    #[serde(skip)]
+    pub reader: Option<ApiKeyInfo>,
+}
+
+/// Claim that the password of a clip was entered before, as kept by the web client.
+///
+/// It only unlocks the clip it was issued for, and only as long as the password stays the same.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unlocked {
+    pub clip_id: DbId,
+    pub password_digest: String,
 }
 
 impl GetClip {
@@ -16,6 +37,8 @@ impl GetClip {
         Self {
             shortcode: ShortCode::from(shortcode),
             password: field::Password::default(),
+            unlock: None,
+            reader: None,
         }
     }
 }
@@ -25,6 +48,8 @@ impl From<ShortCode> for GetClip {
         Self {
             shortcode,
             password: field::Password::default(),
+            unlock: None,
+            reader: None,
         }
     }
 }
@@ -41,6 +66,48 @@ pub struct NewClip {
     pub title: field::Title,
     pub expires: field::Expires,
     pub password: field::Password,
+    #[serde(default)]
+    pub burn_after_reading: field::BurnAfterReading,
+    #[serde(default)]
+    pub max_hits: field::MaxHits,
+    // Detected from the content when not set
+    #[serde(default)]
+    pub language: field::Language,
+    // The content is ciphertext, encrypted by the client
+    #[serde(default)]
+    pub encrypted: field::Encrypted,
+    // Chosen by the creator, generated when not set
+    #[serde(default)]
+    pub shortcode: Option<field::ShortCode>,
+    #[serde(default)]
+    pub visibility: field::Visibility,
+    // Uploaded files arrive as multipart form, never as JSON
+    #[serde(skip)]
+    pub attachment: Option<field::Attachment>,
+    // Set by the server from the API key of the request
+    #[serde(skip)]
+    pub owner: field::Owner,
+    // Generated by the web page, the API relies on the owner instead
+    #[serde(skip)]
+    pub manage_token: field::ManageToken,
+}
+
+/// Proof that the requester may modify a clip
+#[derive(Debug, Clone)]
+pub enum Credential {
+    ApiKey(ApiKeyInfo),
+    ManageToken(field::ManageToken),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeleteClip {
+    pub shortcode: ShortCode,
+}
+
+impl From<ShortCode> for DeleteClip {
+    fn from(shortcode: ShortCode) -> Self {
+        Self { shortcode }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -51,3 +118,105 @@ pub struct UpdateClip {
     pub password: field::Password,
     pub shortcode: field::ShortCode,
 }
+
+/// Order in which clips are listed, based on the date they were posted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Newest,
+    Oldest,
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Newest => write!(f, "newest"),
+            Self::Oldest => write!(f, "oldest"),
+        }
+    }
+}
+
+impl FromStr for SortOrder {
+    type Err = ClipError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "newest" => Ok(Self::Newest),
+            "oldest" => Ok(Self::Oldest),
+            other => Err(ClipError::InvalidSort(other.to_owned())),
+        }
+    }
+}
+
+/// Position of the last clip of a page, the next page starts right after it.
+///
+/// Clips posted within the same second are told apart by their id, so no clip is skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListCursor {
+    pub posted: i64,
+    pub clip_id: DbId,
+}
+
+impl fmt::Display for ListCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let raw = format!("{}:{}", self.posted, self.clip_id);
+        write!(f, "{}", base64::encode_config(raw, base64::URL_SAFE_NO_PAD))
+    }
+}
+
+impl FromStr for ListCursor {
+    type Err = ClipError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ClipError::InvalidCursor(s.to_owned());
+        let raw = base64::decode_config(s, base64::URL_SAFE_NO_PAD).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (posted, clip_id) = raw.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            posted: posted.parse().map_err(|_| invalid())?,
+            clip_id: DbId::from_str(clip_id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Request one page of the clips owned by an API key
+#[derive(Debug, Clone)]
+pub struct ListClips {
+    pub owner: field::Owner,
+    pub limit: u32,
+    pub cursor: Option<ListCursor>,
+    pub sort: SortOrder,
+}
+
+impl ListClips {
+    pub const DEFAULT_LIMIT: u32 = 20;
+    pub const MAX_LIMIT: u32 = 100;
+}
+
+/// Full-text search over the title and content of clips
+#[derive(Debug, Clone)]
+pub struct SearchClips {
+    pub query: String,
+    // Every clip of this owner is included in the results, otherwise only public clips
+    pub owner: field::Owner,
+    pub limit: u32,
+}
+
+/// Request a new API key
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NewApiKey {
+    // Tells keys apart, like the service which uses the key
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires: Option<crate::Time>,
+}
+
+impl Default for NewApiKey {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            scopes: vec![Scope::Read, Scope::Write],
+            expires: None,
+        }
+    }
+}
@@ -1,11 +1,13 @@
 pub mod api;
 pub mod ctx;
 pub mod form;
+pub mod highlight;
 pub mod hit_counter;
 pub mod http;
+pub mod markdown;
 pub mod renderer;
+pub mod unlock;
 
-pub const PASSWORD_COOKIE: &str = "password";
 pub use hit_counter::HitCounter;
 
 // Derive rocket::Responder let us can return this enum directly from a Rocket route
@@ -38,7 +40,13 @@ pub mod test {
     use rocket::local::blocking::Client;
     use tokio::runtime::Handle;
 
+    use crate::data::DatabasePool;
+    use crate::domain::api_key::{ApiKeyInfo, Scope};
+    use crate::domain::clip::field;
+    use crate::service::{action, ask};
     use crate::test::async_runtime;
+    use crate::web::api::{ApiKey, KeySecret};
+    use crate::Clip;
     use crate::RocketConfig;
 
     pub fn init_test_client() -> (tokio::runtime::Runtime, Client) {
@@ -49,24 +57,83 @@ pub mod test {
     }
 
     pub fn config(handle: &Handle) -> RocketConfig {
-        use crate::web::{hit_counter::HitCounter, renderer::Renderer};
+        use crate::web::{highlight::Highlighter, hit_counter::HitCounter, renderer::Renderer};
         let renderer = Renderer::new("templates/".into());
+        let highlighter = Highlighter::new();
         let database = crate::data::test::new_db(handle);
         let maintenance = crate::domain::maintenance::Maintenance::spawn(
             database.get_pool().clone(),
+            Default::default(),
             handle.clone(),
         );
         let hit_counter = HitCounter::new(database.get_pool().clone(), handle.clone());
 
         RocketConfig {
             renderer,
+            highlighter,
             database,
             hit_counter,
             maintenance,
+            limits: Default::default(),
+            shortcode_format: Default::default(),
+            compression: Default::default(),
+            key_secret: Default::default(),
         }
     }
 
     pub fn client(config: RocketConfig) -> Client {
         Client::tracked(crate::rocket(config)).expect("failed to build rocket instance")
     }
+
+    /// Request for a new clip with the given content, every other field is left at its default.
+    pub fn ask_new_clip(content: &str) -> ask::NewClip {
+        ask::NewClip {
+            content: field::Content::new(content).unwrap(),
+            title: field::Title::default(),
+            expires: field::Expires::default(),
+            password: field::Password::default(),
+            burn_after_reading: field::BurnAfterReading::default(),
+            max_hits: field::MaxHits::default(),
+            language: field::Language::default(),
+            encrypted: field::Encrypted::default(),
+            shortcode: None,
+            visibility: field::Visibility::default(),
+            attachment: None,
+            owner: field::Owner::default(),
+            manage_token: field::ManageToken::default(),
+        }
+    }
+
+    /// Store a new clip with the default limits, shortcode format and compression.
+    pub fn create_clip(
+        rt: &tokio::runtime::Runtime,
+        pool: &DatabasePool,
+        req: ask::NewClip,
+    ) -> Clip {
+        let limits = Default::default();
+        let format = Default::default();
+        let compression = Default::default();
+        rt.block_on(action::new_clip(req, &limits, &format, &compression, pool))
+            .expect("failed to create clip")
+    }
+
+    /// Issue an API key with the given scopes, along with what is stored about it.
+    pub fn new_key(
+        rt: &tokio::runtime::Runtime,
+        pool: &DatabasePool,
+        scopes: &[Scope],
+    ) -> (ApiKey, ApiKeyInfo) {
+        let req = ask::NewApiKey {
+            scopes: scopes.to_vec(),
+            ..Default::default()
+        };
+        let secret = KeySecret::default();
+        let api_key = rt
+            .block_on(action::create_api_key(req, &secret, pool))
+            .expect("failed to create api key");
+        let info = rt
+            .block_on(action::get_api_key_info(api_key.clone(), &secret, pool))
+            .expect("failed to read api key");
+        (api_key, info)
+    }
 }
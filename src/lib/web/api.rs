@@ -1,22 +1,56 @@
+use std::fmt;
+use std::marker::PhantomData;
 use std::str::FromStr;
 
-use rocket::form::FromFormField;
+use derive_more::From;
+#[allow(unused_imports)]
+use rocket::form::{Form, FromFormField};
 use rocket::http::{CookieJar, Status};
 use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::status::NoContent;
 use rocket::serde::json::Json;
 use rocket::State;
+#[allow(unused_imports)]
 use rocket::{response, Responder};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::data::compression::Compression;
 use crate::data::AppDatabase;
+use crate::domain::api_key::{ApiKeyInfo, Scope};
+use crate::domain::clip::field::ShortCodeFormat;
+use crate::domain::clip::limits::Limits;
+use crate::domain::clip::{ClipPage, ClipRevision, ClipSummary};
 use crate::service;
 use crate::service::action;
-use crate::web::{HitCounter, PASSWORD_COOKIE};
-use crate::ServiceError;
+use crate::web::form;
+use crate::web::unlock::UnlockToken;
+use crate::web::HitCounter;
+use crate::{ServiceError, ShortCode};
 
+#[allow(unused_imports)]
 use super::hit_counter;
 
 pub const API_KEY_HEADER: &str = "x-api-key";
+pub const PASSWORD_HEADER: &str = "x-clip-password";
+
+/// The password of a password-protected [`Clip`](crate::Clip), supplied through the
+/// [`PASSWORD_HEADER`]. Missing or invalid headers result in an empty password.
+pub struct PasswordHeader(crate::domain::clip::field::Password);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PasswordHeader {
+    type Error = ();
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        use crate::domain::clip::field::Password;
+
+        let password = req
+            .headers()
+            .get_one(PASSWORD_HEADER)
+            .map(|raw_password| Password::new(raw_password.to_string()))
+            .unwrap_or_default();
+        Outcome::Success(PasswordHeader(password))
+    }
+}
 
 #[derive(Responder, Debug, thiserror::Error, Serialize)]
 pub enum ApiKeyError {
@@ -26,6 +60,26 @@ pub enum ApiKeyError {
     #[error("invalid API key format")]
     #[response(status = 400, content_type = "json")]
     DecodeError(String),
+    #[error("API key expired: {0}")]
+    #[response(status = 401, content_type = "json")]
+    Expired(String),
+    #[error("API key revoked: {0}")]
+    #[response(status = 401, content_type = "json")]
+    Revoked(String),
+    #[error("insufficient scope: {0}")]
+    #[response(status = 403, content_type = "json")]
+    InsufficientScope(String),
+}
+
+impl ApiKeyError {
+    /// Status of the response to a request which was rejected with this error
+    pub fn status(&self) -> Status {
+        match self {
+            Self::Expired(_) | Self::Revoked(_) => Status::Unauthorized,
+            Self::InsufficientScope(_) => Status::Forbidden,
+            _ => Status::BadRequest,
+        }
+    }
 }
 
 #[derive(Responder, Debug, thiserror::Error)]
@@ -42,31 +96,133 @@ pub enum ApiError {
     #[response(status = 401, content_type = "json")]
     User(Json<String>),
 
+    #[error("bad request")]
+    #[response(status = 400, content_type = "json")]
+    BadRequest(Json<String>),
+
+    #[error("forbidden")]
+    #[response(status = 403, content_type = "json")]
+    Forbidden(Json<String>),
+
+    #[error("conflict")]
+    #[response(status = 409, content_type = "json")]
+    Conflict(Json<String>),
+
+    #[error("payload too large")]
+    #[response(status = 413, content_type = "json")]
+    PayloadTooLarge(Json<String>),
+
     #[error("key error")]
     #[response(status = 400, content_type = "json")]
     KeyError(Json<ApiKeyError>),
 }
 
+impl ApiError {
+    /// Reject a clip with an invalid field, content over the size limit is reported with `413`.
+    fn invalid_clip(err: crate::ClipError) -> Self {
+        match err {
+            crate::ClipError::ContentTooLarge { .. } => {
+                Self::PayloadTooLarge(Json(err.to_string()))
+            }
+            err => Self::BadRequest(Json(err.to_string())),
+        }
+    }
+}
+
 impl From<ServiceError> for ApiError {
     fn from(err: ServiceError) -> Self {
+        use crate::ClipError;
+
         match err {
+            ServiceError::Clip(
+                e @ (ClipError::ContentTooLarge { .. }
+                | ClipError::TitleTooLong(_)
+                | ClipError::PasswordTooLong(_)
+                | ClipError::InvalidShortCode(_)
+                | ClipError::InvalidVisibility(_)
+                | ClipError::Encryption(_)),
+            ) => Self::invalid_clip(e),
             ServiceError::Clip(c) => Self::User(Json(format!("clip parsing error: {}", c))),
             ServiceError::NotFound => Self::NotFound(Json("entity not found".to_string())),
             ServiceError::Data(_) => Self::Server(Json("a server error occurred".to_string())),
             ServiceError::PermissionError(msg) => Self::User(Json(msg)),
+            ServiceError::Conflict(msg) => Self::Conflict(Json(msg)),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, From)]
 pub struct ApiKey(Vec<u8>);
 
+/// A newly issued [`ApiKey`], in the only response which ever contains the key
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IssuedApiKey {
+    /// The key in base64, to be sent in the [`API_KEY_HEADER`]
+    pub key: String,
+    #[serde(flatten)]
+    pub info: ApiKeyInfo,
+}
+
+/// The [`ApiKey`] to revoke, in base64
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RevokeApiKey {
+    pub key: String,
+}
+
+/// Secret of the keyed hash API keys are stored as. Changing the secret invalidates every key
+/// which was hashed with the previous one.
+#[derive(Clone, Default)]
+pub struct KeySecret(Vec<u8>);
+
+impl KeySecret {
+    pub fn new(secret: &[u8]) -> Self {
+        Self(secret.to_vec())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// Keep the secret out of logs
+impl fmt::Debug for KeySecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeySecret(..)")
+    }
+}
+
+/// Why the [`ApiKey`] of a request was rejected, for the catchers to tell the client
+struct KeyFailure(Option<String>);
+
 impl ApiKey {
+    /// Number of bytes of the key which are stored in the clear, to look the key up by
+    const PREFIX_LENGTH: usize = 4;
+
     pub fn to_base64(&self) -> String {
         // turn a slice of byte into string
         base64::encode(self.0.as_slice())
     }
 
+    /// The start of the key in hex, which is stored next to the hash to find the key by
+    pub fn prefix(&self) -> String {
+        self.0
+            .iter()
+            .take(Self::PREFIX_LENGTH)
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// HMAC-SHA256 of the key, keyed with the server's secret. This is all that gets stored,
+    /// so the database alone doesn't hand out working keys.
+    pub fn hash(&self, secret: &KeySecret) -> Vec<u8> {
+        use hmac::{Hmac, Mac};
+
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(&secret.0)
+            .expect("HMAC accepts keys of any length");
+        mac.update(&self.0);
+        mac.finalize().into_bytes().to_vec()
+    }
+
     pub fn into_inner(self) -> Vec<u8> {
         self.0
     }
@@ -88,106 +244,424 @@ impl FromStr for ApiKey {
     }
 }
 
-/// Allows an [`ApiKey`] to be used as a [request guard](https://rocket.rs/guide/v0.5/requests/#request-guards) in a route.
+/// Look up the [`ApiKey`] of a request and check that it grants `scope`, if there is one.
+async fn authenticate(req: &Request<'_>, scope: Option<Scope>) -> Outcome<ApiKeyInfo, ApiError> {
+    fn server_error() -> Outcome<ApiKeyInfo, ApiError> {
+        Outcome::Error((
+            Status::InternalServerError,
+            ApiError::Server(Json("server error".to_string())),
+        ))
+    }
+    let key_error = |e: ApiKeyError| -> Outcome<ApiKeyInfo, ApiError> {
+        req.local_cache(|| KeyFailure(Some(e.to_string())));
+        Outcome::Error((e.status(), ApiError::KeyError(Json(e))))
+    };
+
+    let key = match req.headers().get_one(API_KEY_HEADER) {
+        Some(key) => key,
+        None => return key_error(ApiKeyError::NotFound("API key not found".to_string())),
+    };
+    let db = match req.guard::<&State<AppDatabase>>().await {
+        Outcome::Success(db) => db,
+        _ => return server_error(),
+    };
+    let secret = match req.guard::<&State<KeySecret>>().await {
+        Outcome::Success(secret) => secret,
+        _ => return server_error(),
+    };
+
+    let api_key = match ApiKey::from_str(key) {
+        Ok(key) => key,
+        Err(e) => return key_error(e),
+    };
+
+    let info = match action::get_api_key_info(api_key, secret, db.get_pool()).await {
+        Ok(info) => info,
+        Err(ServiceError::NotFound) => {
+            return key_error(ApiKeyError::NotFound("API key not found".to_string()))
+        }
+        Err(_) => return server_error(),
+    };
+    if let Some(revoked) = info.revoked.clone() {
+        return key_error(ApiKeyError::Revoked(format!(
+            "the API key was revoked at {}",
+            revoked.into_inner().to_rfc3339()
+        )));
+    }
+    if let Some(expires) = info.expires.clone().filter(|_| info.is_expired()) {
+        return key_error(ApiKeyError::Expired(format!(
+            "the API key expired at {}",
+            expires.into_inner().to_rfc3339()
+        )));
+    }
+    if let Some(scope) = scope.filter(|scope| !info.allows(*scope)) {
+        return key_error(ApiKeyError::InsufficientScope(format!(
+            "the API key needs the {} scope",
+            scope
+        )));
+    }
+    if let Err(e) = action::touch_api_key(&info, db.get_pool()).await {
+        // Failing to record the use is no reason to reject the request
+        eprintln!("failed to record use of API key: {}", e);
+    }
+    Outcome::Success(info)
+}
+
+/// The scope a [`ScopedKey`] has to grant, `None` accepts any valid [`ApiKey`].
+pub trait RequiredScope: Send + Sync + 'static {
+    const SCOPE: Option<Scope>;
+}
+
+pub struct ReadScope;
+pub struct WriteScope;
+pub struct AdminScope;
+pub struct AnyScope;
+
+impl RequiredScope for ReadScope {
+    const SCOPE: Option<Scope> = Some(Scope::Read);
+}
+
+impl RequiredScope for WriteScope {
+    const SCOPE: Option<Scope> = Some(Scope::Write);
+}
+
+impl RequiredScope for AdminScope {
+    const SCOPE: Option<Scope> = Some(Scope::Admin);
+}
+
+impl RequiredScope for AnyScope {
+    const SCOPE: Option<Scope> = None;
+}
+
+/// [Request guard](https://rocket.rs/guide/v0.5/requests/#request-guards) for routes which
+/// need an [`ApiKey`] granting the scope `S` stands for.
+pub struct ScopedKey<S: RequiredScope>(pub ApiKeyInfo, PhantomData<S>);
+
 #[rocket::async_trait]
-impl<'r> FromRequest<'r> for ApiKey {
+impl<'r, S: RequiredScope> FromRequest<'r> for ScopedKey<S> {
     type Error = ApiError;
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        fn server_error() -> Outcome<ApiKey, ApiError> {
-            Outcome::Error((
-                Status::InternalServerError,
-                ApiError::Server(Json("server error".to_string())),
-            ))
-        }
-        fn key_error(e: ApiKeyError) -> Outcome<ApiKey, ApiError> {
-            Outcome::Error((Status::BadRequest, ApiError::KeyError(Json(e))))
-        }
-
-        match req.headers().get_one(API_KEY_HEADER) {
-            None => key_error(ApiKeyError::NotFound("API key not found".to_string())),
-            Some(key) => {
-                let db = match req.guard::<&State<AppDatabase>>().await {
-                    Outcome::Success(db) => db,
-                    _ => return server_error(),
-                };
-
-                let api_key = match ApiKey::from_str(key) {
-                    Ok(key) => key,
-                    Err(e) => return key_error(e),
-                };
-
-                match action::api_key_is_valid(api_key.clone(), db.get_pool()).await {
-                    Ok(valid) if valid => Outcome::Success(api_key),
-                    Ok(valid) if !valid => {
-                        key_error(ApiKeyError::NotFound("API key not found".to_string()))
-                    }
-                    _ => server_error(),
-                }
-            }
-        }
+        authenticate(req, S::SCOPE)
+            .await
+            .map(|info| ScopedKey(info, PhantomData))
     }
 }
 
-/// Route to generate a new [`ApiKey`].
+/// An [`ApiKey`] with the `read` scope
+pub type ReadKey = ScopedKey<ReadScope>;
+/// An [`ApiKey`] with the `write` scope
+pub type WriteKey = ScopedKey<WriteScope>;
+/// An admin [`ApiKey`]
+pub type AdminKey = ScopedKey<AdminScope>;
+/// Any valid [`ApiKey`], whatever its scopes
+pub type ValidKey = ScopedKey<AnyScope>;
+
+/// Route to issue a new [`ApiKey`], for callers with an admin key.
 ///
-/// The key will be logged to the terminal for this demo application.
-#[rocket::get("/key")]
-pub async fn new_api_key(database: &State<AppDatabase>) -> Result<Json<&str>, ApiError> {
-    let api_key = action::generate_api_key(database.get_pool()).await?;
-    println!("API key: {}", api_key.to_base64());
-    Ok(Json("API key generated. See log for details."))
+/// The key is only ever part of this response, the server merely keeps its hash.
+#[rocket::post("/key", data = "<req>")]
+pub async fn new_api_key(
+    req: Json<service::ask::NewApiKey>,
+    database: &State<AppDatabase>,
+    secret: &State<KeySecret>,
+    _admin: AdminKey,
+) -> Result<Json<IssuedApiKey>, ApiError> {
+    let api_key = action::create_api_key(req.into_inner(), secret, database.get_pool()).await?;
+    let info = action::get_api_key_info(api_key.clone(), secret, database.get_pool()).await?;
+    Ok(Json(IssuedApiKey {
+        key: api_key.to_base64(),
+        info,
+    }))
+}
+
+/// Route to revoke an [`ApiKey`].
+///
+/// Keys may revoke themselves, admin keys may revoke any key.
+#[rocket::delete("/key", data = "<req>")]
+pub async fn revoke_api_key(
+    req: Json<RevokeApiKey>,
+    database: &State<AppDatabase>,
+    secret: &State<KeySecret>,
+    caller: ValidKey,
+) -> Result<NoContent, ApiError> {
+    use crate::data::query::RevocationStatus;
+
+    let revoked = ApiKey::from_str(&req.key).map_err(|e| ApiError::KeyError(Json(e)))?;
+    let caller = caller.0;
+    // Other keys only revoke themselves, and learn nothing about whether some other key exists
+    let key_id = Some(&caller.key_id).filter(|_| !caller.is_admin());
+    match action::revoke_api_key(revoked, key_id, secret, database.get_pool()).await? {
+        RevocationStatus::Revoked => Ok(NoContent),
+        RevocationStatus::NotFound if key_id.is_some() => Err(ApiError::Forbidden(Json(
+            "only admin keys may revoke other API keys".to_owned(),
+        ))),
+        RevocationStatus::NotFound => Err(ApiError::NotFound(Json("API key not found".to_owned()))),
+    }
+}
+
+/// A [`ShortCode`] from a link, checked against the configured [`ShortCodeFormat`]. Links which
+/// can't lead to a clip are answered like a missing clip.
+fn parse_shortcode(shortcode: &str, format: &ShortCodeFormat) -> Result<ShortCode, ApiError> {
+    ShortCode::parse(shortcode, format)
+        .map_err(|_| ApiError::NotFound(Json("entity not found".to_owned())))
 }
 
 /// Route to retrieve an existing [`Clip`](crate::domain::Clip), based on it's [`ShortCode`](crate::ShortCode).
+///
+/// Password-protected clips are unlocked either with the [`PASSWORD_HEADER`] or with the
+/// [`UnlockToken`] cookie issued when the password was entered on the web page.
 #[rocket::get("/<shortcode>")]
 pub async fn get_clip(
     shortcode: &str,
+    format: &State<ShortCodeFormat>,
     database: &State<AppDatabase>,
-    cookie: &CookieJar<'_>,
+    cookies: &CookieJar<'_>,
+    password: PasswordHeader,
     hit_counter: &State<HitCounter>,
-    _api_key: ApiKey,
+    api_key: ReadKey,
 ) -> Result<Json<crate::Clip>, ApiError> {
-    use crate::domain::clip::field::Password;
-
+    let shortcode = parse_shortcode(shortcode, format)?;
     let req = service::ask::GetClip {
-        shortcode: shortcode.into(),
-        password: cookie
-            .get(PASSWORD_COOKIE)
-            .map(|cookie| cookie.value())
-            .and_then(|raw_password| Password::new(raw_password.to_string()).ok())
-            .unwrap_or_default(),
+        unlock: UnlockToken::claim(cookies, &shortcode),
+        shortcode: shortcode.clone(),
+        password: password.0,
+        reader: Some(api_key.0),
     };
 
     let clip = action::get_clip(req, database.get_pool()).await?;
-    hit_counter.hit(shortcode.into(), 1);
+    hit_counter.hit_clip(&clip);
     Ok(Json(clip))
 }
 
-/// Route to add a new [`Clip`](crate::Clip).
+/// Route to retrieve a [`Clip`](crate::Clip) in order to modify it.
+///
+/// Only the [`ApiKey`] which created the clip, or an admin key, may read it this way. No
+/// password is needed, and the read is neither counted nor does it burn the clip.
+#[rocket::get("/<shortcode>/edit")]
+pub async fn get_owned_clip(
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    database: &State<AppDatabase>,
+    api_key: ReadKey,
+) -> Result<Json<crate::Clip>, ApiError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    let credential = service::ask::Credential::ApiKey(api_key.0);
+    let clip = action::get_owned_clip(&shortcode, credential, database.get_pool()).await?;
+    Ok(Json(clip))
+}
+
+/// Route to list the clips owned by the [`ApiKey`] of the request, one page at a time.
+///
+/// Clips are sorted by the date they were posted, `newest` (default) or `oldest` first.
+/// The `next_cursor` of a page is passed as `cursor` to fetch the following page.
+#[rocket::get("/?<limit>&<cursor>&<sort>")]
+pub async fn list_clips(
+    limit: Option<u32>,
+    cursor: Option<&str>,
+    sort: Option<&str>,
+    database: &State<AppDatabase>,
+    api_key: ReadKey,
+) -> Result<Json<ClipPage>, ApiError> {
+    use crate::domain::clip::field::Owner;
+    use crate::service::ask::{ListClips, ListCursor, SortOrder};
+
+    let bad_request = |e: crate::ClipError| ApiError::BadRequest(Json(e.to_string()));
+    let cursor = cursor
+        .map(ListCursor::from_str)
+        .transpose()
+        .map_err(bad_request)?;
+    let sort = sort
+        .map(SortOrder::from_str)
+        .transpose()
+        .map_err(bad_request)?
+        .unwrap_or_default();
+
+    let req = ListClips {
+        owner: Owner::new(api_key.0.key_id),
+        limit: limit
+            .unwrap_or(ListClips::DEFAULT_LIMIT)
+            .clamp(1, ListClips::MAX_LIMIT),
+        cursor,
+        sort,
+    };
+    Ok(Json(action::list_clips(req, database.get_pool()).await?))
+}
+
+/// Route to list the most recently posted public clips, newest first.
+#[rocket::get("/recent?<limit>")]
+pub async fn recent_clips(
+    limit: Option<u32>,
+    database: &State<AppDatabase>,
+    _api_key: ReadKey,
+) -> Result<Json<Vec<ClipSummary>>, ApiError> {
+    use crate::service::ask::ListClips;
+
+    let limit = limit
+        .unwrap_or(ListClips::DEFAULT_LIMIT)
+        .clamp(1, ListClips::MAX_LIMIT);
+    Ok(Json(
+        action::recent_clips(limit, database.get_pool()).await?,
+    ))
+}
+
+/// Route to search the title and content of clips.
+///
+/// Clips owned by the [`ApiKey`] of the request are all found, clips of others only when they
+/// are public and have no password, view limit or burn after reading.
+#[rocket::get("/search?<q>&<limit>")]
+pub async fn search_clips(
+    q: &str,
+    limit: Option<u32>,
+    database: &State<AppDatabase>,
+    api_key: ReadKey,
+) -> Result<Json<Vec<ClipSummary>>, ApiError> {
+    use crate::domain::clip::field::Owner;
+    use crate::service::ask::{ListClips, SearchClips};
+
+    let req = SearchClips {
+        query: q.to_owned(),
+        owner: Owner::new(api_key.0.key_id),
+        limit: limit
+            .unwrap_or(ListClips::DEFAULT_LIMIT)
+            .clamp(1, ListClips::MAX_LIMIT),
+    };
+    match action::search_clips(req, database.get_pool()).await {
+        Ok(clips) => Ok(Json(clips)),
+        Err(ServiceError::Clip(e)) => Err(ApiError::BadRequest(Json(e.to_string()))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Route to list the earlier versions of a [`Clip`](crate::Clip), oldest first.
+///
+/// Only the [`ApiKey`] which created the clip, or an admin key, may see its revisions.
+#[rocket::get("/<shortcode>/revisions")]
+pub async fn list_revisions(
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    database: &State<AppDatabase>,
+    api_key: ReadKey,
+) -> Result<Json<Vec<ClipRevision>>, ApiError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    let credential = service::ask::Credential::ApiKey(api_key.0);
+    let revisions = action::list_revisions(&shortcode, credential, database.get_pool()).await?;
+    Ok(Json(revisions))
+}
+
+/// Route to retrieve a single earlier version of a [`Clip`](crate::Clip).
+#[rocket::get("/<shortcode>/revisions/<revision>")]
+pub async fn get_revision(
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    revision: u32,
+    database: &State<AppDatabase>,
+    api_key: ReadKey,
+) -> Result<Json<ClipRevision>, ApiError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    let credential = service::ask::Credential::ApiKey(api_key.0);
+    let revision =
+        action::get_revision(&shortcode, revision, credential, database.get_pool()).await?;
+    Ok(Json(revision))
+}
+
+/// Route to add a new [`Clip`](crate::Clip), owned by the [`ApiKey`] of the request.
 #[rocket::post("/", data = "<req>")]
 pub async fn new_clip(
     req: Json<service::ask::NewClip>,
     database: &State<AppDatabase>,
-    _api_key: ApiKey,
+    limits: &State<Limits>,
+    format: &State<ShortCodeFormat>,
+    compression: &State<Compression>,
+    api_key: WriteKey,
+) -> Result<Json<crate::Clip>, ApiError> {
+    use crate::domain::clip::field::Owner;
+
+    let req = service::ask::NewClip {
+        owner: Owner::new(api_key.0.key_id),
+        ..req.into_inner()
+    };
+    let clip = action::new_clip(req, limits, format, compression, database.get_pool()).await?;
+    Ok(Json(clip))
+}
+
+/// Route to share a file as a new [`Clip`](crate::Clip), owned by the [`ApiKey`] of the request.
+///
+/// The file is sent in the multipart form field `file`, next to the optional fields of a new clip.
+#[rocket::post("/upload", data = "<form>")]
+pub async fn upload_clip(
+    form: Form<form::UploadClip<'_>>,
+    database: &State<AppDatabase>,
+    limits: &State<Limits>,
+    format: &State<ShortCodeFormat>,
+    compression: &State<Compression>,
+    api_key: WriteKey,
 ) -> Result<Json<crate::Clip>, ApiError> {
-    let clip = action::new_clip(req.into_inner(), database.get_pool()).await?;
+    use crate::domain::clip::field::Owner;
+
+    let req = form
+        .into_inner()
+        .into_ask()
+        .await
+        .map_err(ApiError::invalid_clip)?;
+    let req = service::ask::NewClip {
+        owner: Owner::new(api_key.0.key_id),
+        ..req
+    };
+    let clip = action::new_clip(req, limits, format, compression, database.get_pool()).await?;
     Ok(Json(clip))
 }
 
 /// Route to update an existing [`Clip`](crate::Clip).
+///
+/// Only the [`ApiKey`] which created the clip, or an admin key, may update it.
 #[rocket::put("/", data = "<req>")]
 pub async fn update_clip(
     req: Json<service::ask::UpdateClip>,
     database: &State<AppDatabase>,
-    _api_key: ApiKey,
+    limits: &State<Limits>,
+    compression: &State<Compression>,
+    api_key: WriteKey,
 ) -> Result<Json<crate::Clip>, ApiError> {
-    let clip = action::update_clip(req.into_inner(), database.get_pool()).await?;
+    let credential = service::ask::Credential::ApiKey(api_key.0);
+    let req = req.into_inner();
+    let clip =
+        action::update_clip(req, credential, limits, compression, database.get_pool()).await?;
     Ok(Json(clip))
 }
 
+/// Route to delete an existing [`Clip`](crate::Clip), based on it's [`ShortCode`](crate::ShortCode).
+///
+/// Only the [`ApiKey`] which created the clip, or an admin key, may delete it.
+#[rocket::delete("/<shortcode>")]
+pub async fn delete_clip(
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    database: &State<AppDatabase>,
+    api_key: WriteKey,
+) -> Result<NoContent, ApiError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    let credential = service::ask::Credential::ApiKey(api_key.0);
+    action::delete_clip(shortcode.into(), credential, database.get_pool()).await?;
+    Ok(NoContent)
+}
+
 /// The URI [`routes`](rocket::Route) which can be mounted by [`rocket`].
 pub fn routes() -> Vec<rocket::Route> {
-    rocket::routes!(get_clip, new_clip, update_clip, new_api_key)
+    rocket::routes!(
+        get_clip,
+        get_owned_clip,
+        list_clips,
+        recent_clips,
+        search_clips,
+        list_revisions,
+        get_revision,
+        new_clip,
+        upload_clip,
+        update_clip,
+        delete_clip,
+        new_api_key,
+        revoke_api_key
+    )
 }
 
 pub mod catcher {
@@ -212,14 +686,29 @@ pub mod catcher {
         Json("404")
     }
 
+    /// The reason an API key was rejected, if that is why the request failed
+    fn key_failure(req: &Request) -> Option<String> {
+        req.local_cache(|| super::KeyFailure(None)).0.clone()
+    }
+
     #[catch(401)]
-    fn request_error() -> Json<&'static str> {
-        Json("request error")
+    fn request_error(req: &Request) -> Json<String> {
+        Json(key_failure(req).unwrap_or_else(|| "request error".to_owned()))
     }
 
     #[catch(400)]
-    fn missing_api_key() -> Json<&'static str> {
-        Json("API key missing or invalid")
+    fn missing_api_key(req: &Request) -> Json<String> {
+        Json(key_failure(req).unwrap_or_else(|| "API key missing or invalid".to_owned()))
+    }
+
+    #[catch(403)]
+    fn forbidden(req: &Request) -> Json<String> {
+        Json(key_failure(req).unwrap_or_else(|| "forbidden".to_owned()))
+    }
+
+    #[catch(413)]
+    fn payload_too_large() -> Json<&'static str> {
+        Json("payload too large")
     }
 
     pub fn catchers() -> Vec<Catcher> {
@@ -228,7 +717,599 @@ pub mod catcher {
             internal_error,
             not_found,
             request_error,
-            missing_api_key
+            missing_api_key,
+            forbidden,
+            payload_too_large
         ]
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use rocket::http::{Header, Status};
+
+    use crate::data::compression::Compression;
+    use crate::data::AppDatabase;
+    use crate::domain::api_key::Scope;
+    use crate::domain::clip::field;
+    use crate::domain::clip::limits::Limits;
+    use crate::service::{action, ask};
+    use crate::web::api::{ApiKey, KeySecret, API_KEY_HEADER};
+    use crate::web::test::{ask_new_clip, create_clip, init_test_client, new_key};
+
+    #[test]
+    fn test_delete_clip() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (owner, owner_info) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let (other, _) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let req = ask::NewClip {
+            owner: field::Owner::new(owner_info.key_id),
+            ..ask_new_clip("leaked secret")
+        };
+        let clip = create_clip(&rt, pool, req);
+
+        let uri = format!("/api/clip/{}", clip.shortcode.as_str());
+        let delete = |api_key: &ApiKey| {
+            client
+                .delete(&uri)
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .dispatch()
+                .status()
+        };
+        assert_eq!(client.delete(&uri).dispatch().status(), Status::BadRequest);
+        assert_eq!(delete(&other), Status::Unauthorized);
+        assert_eq!(delete(&owner), Status::NoContent);
+        assert_eq!(delete(&owner), Status::NotFound);
+    }
+
+    #[test]
+    fn test_admin_can_delete_any_clip() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (admin, _) = new_key(&rt, pool, &[Scope::Admin]);
+        let req = ask_new_clip("posted on the web");
+        let clip = create_clip(&rt, pool, req);
+
+        let response = client
+            .delete(format!("/api/clip/{}", clip.shortcode.as_str()))
+            .header(Header::new(API_KEY_HEADER, admin.to_base64()))
+            .dispatch();
+        assert_eq!(response.status(), Status::NoContent);
+    }
+
+    #[test]
+    fn test_list_clips() {
+        use crate::domain::clip::ClipPage;
+
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (owner, owner_info) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let (_, other_info) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        for (info, expired) in [
+            (&owner_info, false),
+            (&owner_info, false),
+            (&owner_info, false),
+            (&owner_info, true),
+            (&other_info, false),
+        ] {
+            let req = ask::NewClip {
+                owner: field::Owner::new(info.key_id.clone()),
+                ..ask_new_clip("listed content")
+            };
+            let clip = create_clip(&rt, pool, req);
+            if expired {
+                // expired clips stay stored until the maintenance task deletes them
+                rt.block_on(
+                    sqlx::query("UPDATE clips SET expires = 0 WHERE shortcode = ?")
+                        .bind(clip.shortcode.as_str())
+                        .execute(pool),
+                )
+                .unwrap();
+            }
+        }
+
+        let list = |uri: String| {
+            client
+                .get(uri)
+                .header(Header::new(API_KEY_HEADER, owner.to_base64()))
+                .dispatch()
+        };
+        let response = list("/api/clip?limit=2".to_owned());
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_string().unwrap();
+        assert!(!body.contains("listed content"));
+        let first: ClipPage = serde_json::from_str(&body).unwrap();
+        assert_eq!(first.clips.len(), 2);
+        let cursor = first.next_cursor.expect("missing cursor");
+
+        let second: ClipPage = list(format!("/api/clip?limit=2&cursor={}", cursor))
+            .into_json()
+            .unwrap();
+        assert_eq!(second.clips.len(), 1);
+        assert!(second.next_cursor.is_none());
+        assert!(first
+            .clips
+            .iter()
+            .all(|clip| clip.shortcode.as_str() != second.clips[0].shortcode.as_str()));
+
+        let response = list("/api/clip?cursor=bogus".to_owned());
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn test_search_clips() {
+        use crate::domain::clip::field::Visibility;
+        use crate::domain::clip::ClipSummary;
+
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (owner, owner_info) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let (_, other_info) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let clips = [
+            (
+                &owner_info,
+                "own protected haystack",
+                "secret",
+                Visibility::Unlisted,
+                false,
+            ),
+            (
+                &other_info,
+                "public haystack",
+                "",
+                Visibility::Public,
+                false,
+            ),
+            (
+                &other_info,
+                "foreign protected haystack",
+                "secret",
+                Visibility::Public,
+                false,
+            ),
+            (
+                &other_info,
+                "unlisted haystack",
+                "",
+                Visibility::Unlisted,
+                false,
+            ),
+            (
+                &other_info,
+                "burning haystack",
+                "",
+                Visibility::Public,
+                true,
+            ),
+            (
+                &other_info,
+                "fn main() { deploy-notes.md }",
+                "",
+                Visibility::Public,
+                false,
+            ),
+        ];
+        for (info, content, password, visibility, burn) in clips {
+            let req = ask::NewClip {
+                password: field::Password::new(password.to_owned()),
+                burn_after_reading: field::BurnAfterReading::new(burn),
+                visibility,
+                owner: field::Owner::new(info.key_id.clone()),
+                ..ask_new_clip(content)
+            };
+            create_clip(&rt, pool, req);
+        }
+
+        let search = |query: &str| {
+            client
+                .get(format!("/api/clip/search?q={}", query))
+                .header(Header::new(API_KEY_HEADER, owner.to_base64()))
+                .dispatch()
+        };
+        let found: Vec<ClipSummary> = search("hayst").into_json().unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found.iter().filter(|clip| clip.protected).count(), 1);
+
+        // query syntax is matched literally instead of failing
+        let response = search("haystack%22%20OR");
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(search("").status(), Status::BadRequest);
+        for query in ["fn%20main()", "deploy-notes", "notes.md", "main()%20%7B"] {
+            let found: Vec<ClipSummary> = search(query).into_json().unwrap();
+            assert_eq!(found.len(), 1, "searching for {}", query);
+        }
+        let response = search("()%20-%20%22");
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_revisions() {
+        use crate::domain::clip::ClipRevision;
+
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (owner, owner_info) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let (other, _) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let req = ask::NewClip {
+            owner: field::Owner::new(owner_info.key_id.clone()),
+            ..ask_new_clip("first")
+        };
+        let clip = create_clip(&rt, pool, req);
+        for content in ["second", "third"] {
+            let req = ask::UpdateClip {
+                content: field::Content::new(content).unwrap(),
+                title: field::Title::default(),
+                expires: field::Expires::default(),
+                password: field::Password::default(),
+                shortcode: clip.shortcode.clone(),
+            };
+            let credential = ask::Credential::ApiKey(owner_info.clone());
+            rt.block_on(action::update_clip(
+                req,
+                credential,
+                &Limits::default(),
+                &Compression::default(),
+                pool,
+            ))
+            .unwrap();
+        }
+
+        let get = |uri: String, api_key: &ApiKey| {
+            client
+                .get(uri)
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .dispatch()
+        };
+        let uri = format!("/api/clip/{}/revisions", clip.shortcode.as_str());
+        let revisions: Vec<ClipRevision> = get(uri.clone(), &owner).into_json().unwrap();
+        let contents: Vec<String> = revisions
+            .into_iter()
+            .map(|revision| revision.content.into_inner())
+            .collect();
+        assert_eq!(contents, vec!["first", "second"]);
+        assert_eq!(get(uri.clone(), &other).status(), Status::Unauthorized);
+
+        let revision: ClipRevision = get(format!("{}/2", uri), &owner).into_json().unwrap();
+        assert_eq!(revision.revision, 2);
+        assert_eq!(revision.content.into_inner(), "second");
+        assert_eq!(get(format!("{}/3", uri), &owner).status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_upload_clip() {
+        use rocket::http::ContentType;
+
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (api_key, _) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        let mut body = b"--BOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"shot.png\"\r\n\
+            Content-Type: application/octet-stream\r\n\r\n"
+            .to_vec();
+        body.extend_from_slice(&png);
+        body.extend_from_slice(b"\r\n--BOUNDARY--\r\n");
+        let content_type =
+            ContentType::new("multipart", "form-data").with_params(("boundary", "BOUNDARY"));
+
+        let upload = || {
+            client
+                .post("/api/clip/upload")
+                .header(content_type.clone())
+                .body(body.clone())
+        };
+        assert_eq!(upload().dispatch().status(), Status::BadRequest);
+        let clip: crate::Clip = upload()
+            .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+            .dispatch()
+            .into_json()
+            .unwrap();
+        assert_eq!(clip.content.as_str(), "shot.png");
+
+        let response = client
+            .get(format!("/clip/raw/{}", clip.shortcode.as_str()))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::PNG));
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some("inline; filename*=UTF-8''shot.png")
+        );
+        assert_eq!(response.into_bytes().unwrap(), png);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (api_key, _) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let post = |clip: serde_json::Value| {
+            client
+                .post("/api/clip")
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .body(clip.to_string())
+                .dispatch()
+                .status()
+        };
+        let clip = |content: String, title: &str, password: &str| {
+            serde_json::json!({
+                "content": content,
+                "title": title,
+                "expires": null,
+                "password": password,
+            })
+        };
+
+        assert_eq!(post(clip("fits".to_owned(), "", "")), Status::Ok);
+        let content = "a".repeat(Limits::DEFAULT_CONTENT + 1);
+        assert_eq!(post(clip(content, "", "")), Status::PayloadTooLarge);
+        let title = "t".repeat(Limits::DEFAULT_TITLE + 1);
+        assert_eq!(
+            post(clip("fits".to_owned(), &title, "")),
+            Status::BadRequest
+        );
+        let password = "p".repeat(Limits::DEFAULT_PASSWORD + 1);
+        assert_eq!(
+            post(clip("fits".to_owned(), "", &password)),
+            Status::BadRequest
+        );
+    }
+
+    #[test]
+    fn test_get_owned_clip() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (owner, owner_info) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let (other, _) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let req = ask::NewClip {
+            owner: field::Owner::new(owner_info.key_id),
+            burn_after_reading: field::BurnAfterReading::new(true),
+            password: field::Password::new("secret".to_owned()),
+            ..ask_new_clip("read once")
+        };
+        let clip = create_clip(&rt, pool, req);
+        let get = |api_key: &ApiKey| {
+            client
+                .get(format!("/api/clip/{}/edit", clip.shortcode.as_str()))
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .dispatch()
+        };
+
+        for _ in 0..2 {
+            let response = get(&owner);
+            assert_eq!(response.status(), Status::Ok);
+            assert!(response.into_string().unwrap().contains("read once"));
+        }
+        assert_eq!(get(&other).status(), Status::Unauthorized);
+        let stored = rt
+            .block_on(crate::data::query::get_clip(clip.shortcode.clone(), pool))
+            .unwrap();
+        let stored = crate::Clip::try_from(stored).unwrap();
+        assert_eq!(stored.hits.into_inner(), 0);
+    }
+
+    #[test]
+    fn test_password_hash_not_exposed() {
+        use super::PASSWORD_HEADER;
+
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (api_key, _) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let clip = serde_json::json!({
+            "content": "db credentials",
+            "title": "",
+            "expires": null,
+            "password": "hunter2",
+            "shortcode": "vault",
+        });
+        let created = client
+            .post("/api/clip")
+            .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+            .body(clip.to_string())
+            .dispatch()
+            .into_string()
+            .unwrap();
+        let read = client
+            .get("/api/clip/vault")
+            .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+            .header(Header::new(PASSWORD_HEADER, "hunter2"))
+            .dispatch()
+            .into_string()
+            .unwrap();
+        for body in [created, read] {
+            assert!(body.contains("db credentials"));
+            assert!(!body.contains("argon2"), "{}", body);
+            assert!(!body.contains("hunter2"), "{}", body);
+            let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(json["protected"], true);
+        }
+    }
+
+    #[test]
+    fn test_custom_shortcode() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (api_key, _) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let post = |shortcode: &str| {
+            let clip = serde_json::json!({
+                "content": "release checklist",
+                "title": "",
+                "expires": null,
+                "password": null,
+                "shortcode": shortcode,
+            });
+            client
+                .post("/api/clip")
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .body(clip.to_string())
+                .dispatch()
+                .status()
+        };
+
+        assert_eq!(post("deploy-notes"), Status::Ok);
+        let response = client
+            .get("/api/clip/deploy-notes")
+            .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+            .dispatch();
+        assert!(response
+            .into_string()
+            .unwrap()
+            .contains("release checklist"));
+        assert_eq!(post("deploy-notes"), Status::Conflict);
+        assert_eq!(post("search"), Status::BadRequest);
+        assert_eq!(post("Deploy Notes"), Status::BadRequest);
+    }
+
+    #[test]
+    fn test_visibility() {
+        use crate::domain::clip::ClipSummary;
+
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (owner, owner_info) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let (other, _) = new_key(&rt, pool, &[Scope::Read, Scope::Write]);
+        let new_clip = |title: &str, visibility: field::Visibility, password: Option<&str>| {
+            let req = ask::NewClip {
+                title: field::Title::new(Some(title.to_owned())),
+                password: field::Password::new(password.map(str::to_owned)),
+                visibility,
+                owner: field::Owner::new(owner_info.key_id.clone()),
+                ..ask_new_clip("wall of snippets")
+            };
+            create_clip(&rt, pool, req)
+        };
+        new_clip("public", field::Visibility::Public, None);
+        new_clip("protected", field::Visibility::Public, Some("secret"));
+        new_clip("unlisted", field::Visibility::Unlisted, None);
+        let private = new_clip("private", field::Visibility::Private, None);
+
+        let get = |uri: String, api_key: &ApiKey| {
+            client
+                .get(uri)
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .dispatch()
+        };
+        let recent: Vec<ClipSummary> = get("/api/clip/recent".to_owned(), &other)
+            .into_json()
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].title.clone().into_inner().unwrap(), "public");
+
+        let uri = format!("/api/clip/{}", private.shortcode.as_str());
+        assert_eq!(get(uri.clone(), &other).status(), Status::NotFound);
+        assert_eq!(get(uri, &owner).status(), Status::Ok);
+        let page = format!("/clip/{}", private.shortcode.as_str());
+        assert_ne!(client.get(page).dispatch().status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_api_key_scopes() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let issue_key = |scopes: Vec<Scope>, expires: Option<crate::Time>| {
+            let req = ask::NewApiKey {
+                name: "ci".to_owned(),
+                scopes,
+                expires,
+            };
+            rt.block_on(action::create_api_key(req, &KeySecret::default(), pool))
+                .unwrap()
+        };
+        let reader = issue_key(vec![Scope::Read], None);
+        let yesterday = chrono::Utc::now() - chrono::Duration::days(1);
+        let expired = issue_key(vec![Scope::Read, Scope::Write], Some(yesterday.into()));
+
+        let list = |api_key: &ApiKey| {
+            client
+                .get("/api/clip")
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .dispatch()
+        };
+        let post = |api_key: &ApiKey| {
+            let clip = serde_json::json!({
+                "content": "deployed",
+                "title": "",
+                "expires": null,
+                "password": null,
+            });
+            client
+                .post("/api/clip")
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .body(clip.to_string())
+                .dispatch()
+        };
+
+        assert_eq!(list(&reader).status(), Status::Ok);
+        let response = post(&reader);
+        assert_eq!(response.status(), Status::Forbidden);
+        assert!(response.into_string().unwrap().contains("write"));
+        assert_eq!(list(&expired).status(), Status::Unauthorized);
+        assert_eq!(post(&expired).status(), Status::Unauthorized);
+
+        let info = rt
+            .block_on(action::get_api_key_info(
+                reader,
+                &KeySecret::default(),
+                pool,
+            ))
+            .unwrap();
+        assert_eq!(info.name, "ci");
+        assert!(info.last_used.is_some());
+        assert!(!info.is_admin());
+    }
+
+    #[test]
+    fn test_issue_and_revoke_api_key() {
+        use crate::web::api::IssuedApiKey;
+        use std::str::FromStr;
+
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let (admin, _) = new_key(&rt, pool, &[Scope::Admin]);
+        // Revoking itself takes no particular scope
+        let (user, _) = new_key(&rt, pool, &[Scope::Read]);
+        let issue = |api_key: &ApiKey| {
+            client
+                .post("/api/clip/key")
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .body(r#"{"name": "deploy bot", "scopes": ["read"]}"#)
+                .dispatch()
+        };
+        let revoke = |revoked: &ApiKey, api_key: &ApiKey| {
+            client
+                .delete("/api/clip/key")
+                .header(Header::new(API_KEY_HEADER, api_key.to_base64()))
+                .body(serde_json::json!({ "key": revoked.to_base64() }).to_string())
+                .dispatch()
+                .status()
+        };
+
+        assert_eq!(issue(&user).status(), Status::Forbidden);
+        assert_eq!(
+            client.post("/api/clip/key").dispatch().status(),
+            Status::BadRequest
+        );
+        let issued: IssuedApiKey = issue(&admin).into_json().unwrap();
+        assert_eq!(issued.info.name, "deploy bot");
+        let bot = ApiKey::from_str(&issued.key).unwrap();
+        let list = client
+            .get("/api/clip")
+            .header(Header::new(API_KEY_HEADER, issued.key.clone()))
+            .dispatch();
+        assert_eq!(list.status(), Status::Ok);
+
+        assert_eq!(revoke(&bot, &user), Status::Forbidden);
+        // Keys which don't exist get the same answer
+        assert_eq!(revoke(&ApiKey::default(), &user), Status::Forbidden);
+        assert_eq!(revoke(&user, &user), Status::NoContent);
+        let response = client
+            .get("/api/clip")
+            .header(Header::new(API_KEY_HEADER, user.to_base64()))
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert!(response.into_string().unwrap().contains("revoked"));
+        assert_eq!(revoke(&bot, &admin), Status::NoContent);
+        assert_eq!(revoke(&bot, &admin), Status::NotFound);
+    }
+}
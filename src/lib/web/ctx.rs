@@ -1,6 +1,9 @@
 use derive_more::Constructor;
 use serde::Serialize;
 
+use crate::domain::clip::{field, ClipSummary};
+use crate::domain::diff::DiffLine;
+
 pub trait PageContext {
     fn title(&self) -> &str;
     fn template_path(&self) -> &str;
@@ -25,6 +28,11 @@ impl PageContext for Home {
 #[derive(Debug, Serialize, Constructor)]
 pub struct ViewClip {
     pub clip: crate::Clip,
+    // Content of the clip as HTML, syntax highlighted or rendered from Markdown
+    pub rendered: String,
+    // Uploaded files are embedded as data URI, images are shown as preview
+    pub data_uri: Option<String>,
+    pub image: bool,
 }
 
 impl PageContext for ViewClip {
@@ -39,6 +47,24 @@ impl PageContext for ViewClip {
     }
 }
 
+#[derive(Debug, Serialize, Constructor)]
+pub struct ClipCreated {
+    shortcode: crate::ShortCode,
+    manage_token: field::ManageToken,
+}
+
+impl PageContext for ClipCreated {
+    fn template_path(&self) -> &str {
+        "clip_created"
+    }
+    fn title(&self) -> &str {
+        "Clip Created"
+    }
+    fn parent(&self) -> &str {
+        "base"
+    }
+}
+
 #[derive(Debug, Serialize, Constructor)]
 pub struct PasswordRequired {
     shortcode: crate::ShortCode,
@@ -55,3 +81,119 @@ impl PageContext for PasswordRequired {
         "base"
     }
 }
+
+/// Page to edit a [`Clip`](crate::Clip) with its management token.
+///
+/// The clip is only present once the token was checked, before that the page asks for it.
+#[derive(Debug, Serialize, Constructor)]
+pub struct EditClip {
+    shortcode: crate::ShortCode,
+    manage_token: field::ManageToken,
+    clip: Option<crate::Clip>,
+}
+
+impl PageContext for EditClip {
+    fn template_path(&self) -> &str {
+        "clip_edit"
+    }
+    fn title(&self) -> &str {
+        "Edit Clip"
+    }
+    fn parent(&self) -> &str {
+        "base"
+    }
+}
+
+#[derive(Debug, Serialize, Constructor)]
+pub struct DeleteClip {
+    shortcode: crate::ShortCode,
+    deleted: bool,
+}
+
+impl PageContext for DeleteClip {
+    fn template_path(&self) -> &str {
+        "clip_delete"
+    }
+    fn title(&self) -> &str {
+        "Delete Clip"
+    }
+    fn parent(&self) -> &str {
+        "base"
+    }
+}
+
+/// Results of a full-text search, empty until something was searched for.
+#[derive(Debug, Serialize, Constructor)]
+pub struct Search {
+    query: String,
+    clips: Vec<ClipSummary>,
+}
+
+impl PageContext for Search {
+    fn template_path(&self) -> &str {
+        "search"
+    }
+    fn title(&self) -> &str {
+        "Search Clips"
+    }
+    fn parent(&self) -> &str {
+        "base"
+    }
+}
+
+/// The most recently posted public clips, newest first.
+#[derive(Debug, Serialize, Constructor)]
+pub struct Recent {
+    clips: Vec<ClipSummary>,
+}
+
+impl PageContext for Recent {
+    fn template_path(&self) -> &str {
+        "recent"
+    }
+    fn title(&self) -> &str {
+        "Recent Clips"
+    }
+    fn parent(&self) -> &str {
+        "base"
+    }
+}
+
+/// Line diff between two versions of a [`Clip`](crate::Clip).
+///
+/// Versions are numbered like revisions, the current version comes after the last revision.
+/// Without any versions the page asks for the management token first.
+#[derive(Debug, Serialize, Constructor)]
+pub struct ClipHistory {
+    shortcode: crate::ShortCode,
+    manage_token: field::ManageToken,
+    versions: Vec<u32>,
+    from: u32,
+    to: u32,
+    lines: Vec<DiffLine>,
+}
+
+impl ClipHistory {
+    pub fn locked(shortcode: crate::ShortCode) -> Self {
+        Self::new(
+            shortcode,
+            field::ManageToken::default(),
+            vec![],
+            0,
+            0,
+            vec![],
+        )
+    }
+}
+
+impl PageContext for ClipHistory {
+    fn template_path(&self) -> &str {
+        "clip_history"
+    }
+    fn title(&self) -> &str {
+        "Clip History"
+    }
+    fn parent(&self) -> &str {
+        "base"
+    }
+}
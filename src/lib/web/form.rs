@@ -1,7 +1,10 @@
-use rocket::form::FromForm;
+use rocket::form::{self, FromForm};
+use rocket::fs::TempFile;
 use serde::Serialize;
 
 use crate::domain::clip::field;
+use crate::service::ask;
+use crate::ClipError;
 
 #[derive(Debug, Serialize, FromForm)]
 pub struct NewClip {
@@ -9,9 +12,120 @@ pub struct NewClip {
     pub title: field::Title,
     pub expires: field::Expires,
     pub password: field::Password,
+    pub burn_after_reading: field::BurnAfterReading,
+    pub max_hits: field::MaxHits,
+    pub language: field::Language,
+    pub encrypted: field::Encrypted,
+    // Left empty to get a generated shortcode
+    #[field(default = String::new())]
+    #[field(validate = custom_shortcode())]
+    pub shortcode: String,
+    pub visibility: field::Visibility,
+}
+
+impl NewClip {
+    /// The shortcode chosen for the clip, if any
+    pub fn custom_shortcode(&self) -> Option<field::ShortCode> {
+        optional_shortcode(&self.shortcode)
+    }
 }
 
 #[derive(Debug, Serialize, FromForm)]
 pub struct GetPasswordProtectedClip {
     pub password: field::Password,
 }
+
+#[derive(Debug, Serialize, FromForm)]
+pub struct ManageClip {
+    pub token: field::ManageToken,
+}
+
+/// Versions of a clip to compare, authorized by its management token.
+#[derive(Debug, Serialize, FromForm)]
+pub struct ClipHistory {
+    pub token: field::ManageToken,
+    pub from: Option<u32>,
+    pub to: Option<u32>,
+}
+
+#[derive(Debug, Serialize, FromForm)]
+pub struct EditClip {
+    pub token: field::ManageToken,
+    pub content: field::Content,
+    pub title: field::Title,
+    pub expires: field::Expires,
+    // Left empty to keep the current password
+    pub password: field::Password,
+    pub remove_password: bool,
+}
+
+/// A file shared through a multipart upload, with the same options as [`NewClip`].
+///
+/// Optional fields may be left out of a multipart upload, the fields fall back to the default
+/// of their `FromFormField` implementation.
+#[derive(Debug, FromForm)]
+pub struct UploadClip<'r> {
+    pub file: TempFile<'r>,
+    pub title: field::Title,
+    pub expires: field::Expires,
+    pub password: field::Password,
+    pub burn_after_reading: field::BurnAfterReading,
+    pub max_hits: field::MaxHits,
+    #[field(default = String::new())]
+    #[field(validate = custom_shortcode())]
+    pub shortcode: String,
+    pub visibility: field::Visibility,
+}
+
+impl UploadClip<'_> {
+    /// Read the uploaded file and turn the upload into a request for a new clip.
+    ///
+    /// The content of the clip is the name of the file, so it can be found by search.
+    pub async fn into_ask(self) -> Result<ask::NewClip, ClipError> {
+        use tokio::io::AsyncReadExt;
+
+        let mut data = Vec::with_capacity(self.file.len() as usize);
+        self.file
+            .open()
+            .await
+            .map_err(|e| ClipError::InvalidAttachment(e.to_string()))?
+            .read_to_end(&mut data)
+            .await
+            .map_err(|e| ClipError::InvalidAttachment(e.to_string()))?;
+        let filename = self
+            .file
+            .raw_name()
+            .map(|name| name.dangerous_unsafe_unsanitized_raw().as_str().to_owned());
+        let attachment = field::Attachment::new(data, filename)?;
+
+        Ok(ask::NewClip {
+            content: field::Content::new(attachment.display_name())?,
+            title: self.title,
+            expires: self.expires,
+            password: self.password,
+            burn_after_reading: self.burn_after_reading,
+            max_hits: self.max_hits,
+            language: Default::default(),
+            encrypted: Default::default(),
+            shortcode: optional_shortcode(&self.shortcode),
+            visibility: self.visibility,
+            attachment: Some(attachment),
+            owner: field::Owner::default(),
+            manage_token: Default::default(),
+        })
+    }
+}
+
+/// Check a shortcode entered into a form, an empty field leaves the choice to the server.
+fn custom_shortcode<'v>(shortcode: &str) -> form::Result<'v, ()> {
+    if shortcode.trim().is_empty() {
+        return Ok(());
+    }
+    field::ShortCode::custom(shortcode)
+        .map(|_| ())
+        .map_err(|e| form::Error::validation(e.to_string()).into())
+}
+
+fn optional_shortcode(shortcode: &str) -> Option<field::ShortCode> {
+    field::ShortCode::custom(shortcode).ok()
+}
@@ -0,0 +1,65 @@
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+use crate::domain::clip::field::{Content, Language};
+
+/// Theme used for highlighting, it matches the light colors of the pages
+const THEME: &str = "InspiredGitHub";
+
+/// Server-side syntax highlighting of clip content.
+///
+/// Loading the syntax definitions takes a while, so it happens once when the server starts.
+pub struct Highlighter {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+
+impl Highlighter {
+    pub fn new() -> Self {
+        let mut themes = ThemeSet::load_defaults();
+        Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            theme: themes
+                .themes
+                .remove(THEME)
+                .expect("missing syntax highlighting theme"),
+        }
+    }
+
+    /// Render the content as HTML. Unknown languages are rendered as plain text.
+    pub fn highlight(&self, content: &Content, language: &Language) -> String {
+        let syntax = language
+            .as_deref()
+            .and_then(|language| self.syntaxes.find_syntax_by_token(language))
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text());
+        highlighted_html_for_string(content.as_str(), &self.syntaxes, syntax, &self.theme)
+            .unwrap_or_else(|e| {
+                eprintln!("highlighting error: {}", e);
+                format!("<pre>{}</pre>", handlebars::html_escape(content.as_str()))
+            })
+    }
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Highlighter;
+    use crate::domain::clip::field::{Content, Language};
+
+    #[test]
+    fn test_highlight_escapes_content() {
+        let highlighter = Highlighter::new();
+        let content = Content::new("fn main() { println!(\"<b>\"); }").unwrap();
+        let html = highlighter.highlight(&content, &Language::new("rust".to_owned()).unwrap());
+        assert!(html.starts_with("<pre"));
+        assert!(html.contains("&lt;b&gt;"));
+        let html = highlighter.highlight(&content, &Language::new("nope".to_owned()).unwrap());
+        assert!(!html.contains("<b>"));
+    }
+}
@@ -2,13 +2,14 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+#[allow(unused_imports)]
 use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
 use parking_lot::Mutex;
 use tokio::runtime::Handle;
 
 use crate::data::DatabasePool;
 use crate::service::{self, ServiceError};
-use crate::ShortCode;
+use crate::{Clip, ShortCode};
 
 /// Thread-safe shared storage of pending hits.
 type HitStore = Arc<Mutex<HashMap<ShortCode, u32>>>;
@@ -122,4 +123,14 @@ impl HitCounter {
             eprintln!("hit count error: {}", e)
         }
     }
+
+    /// Count a single view of a [`Clip`].
+    ///
+    /// Clips with a view limit are skipped, since their hits are already persisted by the
+    /// service layer when they are read.
+    pub fn hit_clip(&self, clip: &Clip) {
+        if !clip.max_hits.is_limited() {
+            self.hit(clip.shortcode.clone(), 1)
+        }
+    }
 }
@@ -1,16 +1,145 @@
-use rocket::form::{Contextual, Form};
-use rocket::http::{Cookie, CookieJar, Status};
+use rocket::form::{Context, Contextual, Form};
+use std::io::Cursor;
+
+use rocket::http::{ContentType, Cookie, CookieJar, SameSite, Status};
+#[allow(unused_imports)]
 use rocket::response::content::{self, RawHtml};
-use rocket::response::{status, Redirect};
-use rocket::{uri, State};
+use rocket::response::{self, status, Redirect, Responder, Response};
+use rocket::{uri, Request, State};
 
+use crate::data::compression::Compression;
 use crate::data::AppDatabase;
+use crate::domain::clip::field::{self, ShortCodeFormat};
+use crate::domain::clip::limits::Limits;
 use crate::service;
 use crate::service::action;
-use crate::web::{
-    ctx, form, hit_counter::HitCounter, renderer::Renderer, PageError, PASSWORD_COOKIE,
-};
-use crate::{ServiceError, ShortCode};
+use crate::web::highlight::Highlighter;
+use crate::web::markdown;
+use crate::web::unlock::UnlockToken;
+use crate::web::{ctx, form, hit_counter::HitCounter, renderer::Renderer, PageError};
+use crate::{ClipError, ServiceError, ShortCode};
+
+/// Prefix of the private cookie which carries a newly generated management token to the
+/// clip page. It is removed as soon as the token was shown.
+const MANAGE_TOKEN_COOKIE_PREFIX: &str = "manage-token-";
+
+fn manage_token_cookie(shortcode: &ShortCode) -> String {
+    format!("{}{}", MANAGE_TOKEN_COOKIE_PREFIX, shortcode.as_str())
+}
+
+/// Response of [`new_clip`]: burn-after-reading clips must not be opened by their creator,
+/// so they get a page with the link instead of a redirect to the clip.
+#[derive(rocket::Responder)]
+pub enum NewClipResponse {
+    Redirect(Box<Redirect>),
+    Created(RawHtml<String>),
+}
+
+/// Collect the messages of a form which failed to parse, together with the status to respond
+/// with: `413` when a field exceeds its size limit, `400` otherwise.
+fn form_errors(context: &Context<'_>) -> (Status, Vec<String>) {
+    use rocket::form::error::ErrorKind;
+
+    let messages = context
+        .errors()
+        .map(|err| match &err.kind {
+            ErrorKind::Validation(msg) => msg.to_string(),
+            ErrorKind::Custom(_, e) => e.to_string(),
+            // Rocket's own limits on the size of the submitted data
+            _ if err.status() == Status::PayloadTooLarge => {
+                "The submitted data is too large".to_owned()
+            }
+            _ => {
+                eprintln!("unhandled error: {}", err);
+                "An error occurred, please try again".to_owned()
+            }
+        })
+        .collect();
+    let status = if context
+        .errors()
+        .any(|err| err.status() == Status::PayloadTooLarge)
+    {
+        Status::PayloadTooLarge
+    } else {
+        Status::BadRequest
+    };
+    (status, messages)
+}
+
+/// Prepare the view page of a clip. Markdown clips are rendered instead of being highlighted,
+/// while the raw route always returns the source.
+///
+/// Uploaded files are embedded in the page, since viewing the page may be the only hit a
+/// burn-after-reading clip gets. Encrypted clips are decrypted by the page itself.
+fn view_clip(clip: crate::Clip, highlighter: &Highlighter) -> ctx::ViewClip {
+    match &clip.attachment {
+        Some(attachment) => {
+            let data_uri = format!(
+                "data:{};base64,{}",
+                attachment.essence(),
+                base64::encode(attachment.data())
+            );
+            let image = attachment.is_image();
+            ctx::ViewClip::new(clip, String::new(), Some(data_uri), image)
+        }
+        None if clip.encrypted.is_set() => ctx::ViewClip::new(clip, String::new(), None, false),
+        None if clip.language.is_markdown() => {
+            let rendered = markdown::render(&clip.content);
+            ctx::ViewClip::new(clip, rendered, None, false)
+        }
+        None => {
+            let rendered = highlighter.highlight(&clip.content, &clip.language);
+            ctx::ViewClip::new(clip, rendered, None, false)
+        }
+    }
+}
+
+/// Response of [`get_raw_clip`]: the text of a clip, or an uploaded file with its own type.
+pub enum RawClip {
+    Text(String),
+    File(field::Attachment),
+}
+
+impl<'r> Responder<'r, 'static> for RawClip {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let attachment = match self {
+            RawClip::Text(content) => return content.respond_to(req),
+            RawClip::File(attachment) => attachment,
+        };
+        let content_type =
+            ContentType::parse_flexible(attachment.mime_type()).unwrap_or(ContentType::Binary);
+        // Types which could run scripts in our origin are always downloaded
+        let disposition = format!(
+            "{}; filename*=UTF-8''{}",
+            if attachment.is_inline() {
+                "inline"
+            } else {
+                "attachment"
+            },
+            percent_encode(attachment.display_name())
+        );
+        let (data, _, _) = attachment.into_parts();
+        Response::build()
+            .header(content_type)
+            .raw_header("Content-Disposition", disposition)
+            .raw_header("X-Content-Type-Options", "nosniff")
+            .sized_body(data.len(), Cursor::new(data))
+            .ok()
+    }
+}
+
+/// Encode a header parameter as described in RFC 5987.
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            other => format!("%{:02X}", other),
+        })
+        .collect()
+}
 
 /// Route to the home page.
 #[rocket::get("/")]
@@ -19,54 +148,100 @@ fn home(renderer: &State<Renderer<'_>>) -> RawHtml<String> {
     RawHtml(renderer.render(context, &[]))
 }
 
+/// Route to list the most recently posted public clips.
+#[rocket::get("/recent")]
+pub async fn recent(
+    database: &State<AppDatabase>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<RawHtml<String>, PageError> {
+    let limit = service::ask::ListClips::DEFAULT_LIMIT;
+    match action::recent_clips(limit, database.get_pool()).await {
+        Ok(clips) => Ok(RawHtml(renderer.render(ctx::Recent::new(clips), &[]))),
+        Err(_) => Err(PageError::Internal("server error".to_owned())),
+    }
+}
+
+/// Route to search the title and content of clips.
+///
+/// Visitors of the web page don't own any clip, so they only find public clips without a
+/// password or a view limit.
+#[rocket::get("/search?<q>")]
+pub async fn search(
+    q: Option<&str>,
+    database: &State<AppDatabase>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<RawHtml<String>, PageError> {
+    let query = q.unwrap_or_default().trim();
+    if query.is_empty() {
+        let context = ctx::Search::new(String::new(), vec![]);
+        return Ok(RawHtml(renderer.render(context, &[])));
+    }
+
+    let req = service::ask::SearchClips {
+        query: query.to_owned(),
+        owner: field::Owner::default(),
+        limit: service::ask::ListClips::DEFAULT_LIMIT,
+    };
+    match action::search_clips(req, database.get_pool()).await {
+        Ok(clips) => {
+            let context = ctx::Search::new(query.to_owned(), clips);
+            Ok(RawHtml(renderer.render(context, &[])))
+        }
+        Err(ServiceError::Clip(e)) => {
+            let context = ctx::Search::new(query.to_owned(), vec![]);
+            Ok(RawHtml(renderer.render(context, &[e.to_string().as_str()])))
+        }
+        Err(_) => Err(PageError::Internal("server error".to_owned())),
+    }
+}
+
 /// Route to submit a new [`Clip`](crate::Clip).
 #[rocket::post("/", data = "<form>")]
 pub async fn new_clip(
+    cookies: &CookieJar<'_>,
     form: Form<Contextual<'_, form::NewClip>>,
     database: &State<AppDatabase>,
+    limits: &State<Limits>,
+    format: &State<ShortCodeFormat>,
+    compression: &State<Compression>,
     renderer: &State<Renderer<'_>>,
-) -> Result<Redirect, (Status, RawHtml<String>)> {
+) -> Result<NewClipResponse, (Status, RawHtml<String>)> {
     // Throw away Form type and work with Contextual type
     let form = form.into_inner();
 
     // Deal with valid form
     if let Some(value) = form.value {
+        let shortcode = value.custom_shortcode();
         let req = service::ask::NewClip {
             content: value.content,
             title: value.title,
             expires: value.expires,
             password: value.password,
+            burn_after_reading: value.burn_after_reading,
+            max_hits: value.max_hits,
+            language: value.language,
+            encrypted: value.encrypted,
+            shortcode,
+            visibility: value.visibility,
+            attachment: None,
+            owner: field::Owner::default(),
+            manage_token: field::ManageToken::default(),
         };
-
-        match action::new_clip(req, database.get_pool()).await {
-            Ok(clip) => Ok(Redirect::to(uri!(get_clip(shortcode = clip.shortcode)))),
-            Err(e) => {
-                eprintln!("internal error: {}", e);
-                Err((
-                    Status::InternalServerError,
-                    RawHtml(renderer.render(
-                        ctx::Home::default(),
-                        &["A server error occurred. Please try again"],
-                    )),
-                ))
-            }
-        }
+        create_clip(
+            req,
+            cookies,
+            database,
+            limits,
+            format,
+            compression,
+            renderer,
+        )
+        .await
     } else {
-        let errors = form
-            .context
-            .errors()
-            .map(|err| {
-                use rocket::form::error::ErrorKind;
-                if let ErrorKind::Validation(msg) = &err.kind {
-                    msg.as_ref()
-                } else {
-                    eprintln!("unhandled error: {}", err);
-                    "An error occurred, please try again"
-                }
-            })
-            .collect::<Vec<_>>();
+        let (status, errors) = form_errors(&form.context);
+        let errors: Vec<&str> = errors.iter().map(String::as_str).collect();
         Err((
-            Status::BadRequest,
+            status,
             RawHtml(renderer.render_with_data(
                 ctx::Home::default(),
                 ("clip", &form.context),
@@ -76,35 +251,172 @@ pub async fn new_clip(
     }
 }
 
+/// Route to share a file as a new [`Clip`](crate::Clip).
+#[rocket::post("/upload", data = "<form>")]
+pub async fn upload_clip(
+    cookies: &CookieJar<'_>,
+    form: Form<Contextual<'_, form::UploadClip<'_>>>,
+    database: &State<AppDatabase>,
+    limits: &State<Limits>,
+    format: &State<ShortCodeFormat>,
+    compression: &State<Compression>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<NewClipResponse, (Status, RawHtml<String>)> {
+    let form = form.into_inner();
+    let render_error = |status, errors: &[&str]| {
+        (
+            status,
+            RawHtml(renderer.render(ctx::Home::default(), errors)),
+        )
+    };
+
+    match form.value {
+        Some(value) => match value.into_ask().await {
+            Ok(req) => {
+                create_clip(
+                    req,
+                    cookies,
+                    database,
+                    limits,
+                    format,
+                    compression,
+                    renderer,
+                )
+                .await
+            }
+            Err(e @ ClipError::ContentTooLarge { .. }) => Err(render_error(
+                Status::PayloadTooLarge,
+                &[e.to_string().as_str()],
+            )),
+            Err(e) => Err(render_error(Status::BadRequest, &[e.to_string().as_str()])),
+        },
+        None => {
+            let (status, errors) = form_errors(&form.context);
+            let errors: Vec<&str> = errors.iter().map(String::as_str).collect();
+            Err(render_error(status, &errors))
+        }
+    }
+}
+
+/// Save a new clip posted on the web page and show it to its creator, together with the
+/// management token which is generated for it.
+async fn create_clip(
+    req: service::ask::NewClip,
+    cookies: &CookieJar<'_>,
+    database: &State<AppDatabase>,
+    limits: &State<Limits>,
+    format: &State<ShortCodeFormat>,
+    compression: &State<Compression>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<NewClipResponse, (Status, RawHtml<String>)> {
+    // The token is shown to the creator once, only its hash is stored
+    let manage_token = field::ManageToken::generate();
+    let req = service::ask::NewClip {
+        manage_token: manage_token.clone(),
+        ..req
+    };
+
+    match action::new_clip(req, limits, format, compression, database.get_pool()).await {
+        Ok(clip) if clip.burn_after_reading.is_set() => {
+            let context = ctx::ClipCreated::new(clip.shortcode, manage_token);
+            Ok(NewClipResponse::Created(RawHtml(
+                renderer.render(context, &[]),
+            )))
+        }
+        Ok(clip) => {
+            let mut cookie = Cookie::new(
+                manage_token_cookie(&clip.shortcode),
+                manage_token.into_inner().unwrap_or_default(),
+            );
+            cookie.set_path("/clip");
+            cookie.set_http_only(true);
+            cookie.set_same_site(SameSite::Strict);
+            cookies.add_private(cookie);
+            let uri = uri!(get_clip(shortcode = clip.shortcode.as_str()));
+            Ok(NewClipResponse::Redirect(Box::new(Redirect::to(uri))))
+        }
+        Err(ServiceError::Clip(e)) => {
+            let status = match e {
+                ClipError::ContentTooLarge { .. } => Status::PayloadTooLarge,
+                _ => Status::BadRequest,
+            };
+            Err((
+                status,
+                RawHtml(renderer.render(ctx::Home::default(), &[e.to_string().as_str()])),
+            ))
+        }
+        Err(ServiceError::Conflict(msg)) => Err((
+            Status::Conflict,
+            RawHtml(renderer.render(ctx::Home::default(), &[msg.as_str()])),
+        )),
+        Err(e) => {
+            eprintln!("internal error: {}", e);
+            Err((
+                Status::InternalServerError,
+                RawHtml(renderer.render(
+                    ctx::Home::default(),
+                    &["A server error occurred. Please try again"],
+                )),
+            ))
+        }
+    }
+}
+
+/// A [`ShortCode`] from a link, checked against the configured [`ShortCodeFormat`]. Links which
+/// can't lead to a clip are answered like a missing clip.
+fn parse_shortcode(shortcode: &str, format: &ShortCodeFormat) -> Result<ShortCode, PageError> {
+    ShortCode::parse(shortcode, format)
+        .map_err(|_| PageError::NotFound("clip not found".to_owned()))
+}
+
 /// Route to get a [`Clip`](crate::Clip).
+///
+/// Right after the clip was created, its management token is shown once.
 #[rocket::get("/clip/<shortcode>")]
 pub async fn get_clip(
-    shortcode: ShortCode,
+    cookies: &CookieJar<'_>,
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
     database: &State<AppDatabase>,
     hit_counter: &State<HitCounter>,
+    highlighter: &State<Highlighter>,
     renderer: &State<Renderer<'_>>,
 ) -> Result<status::Custom<RawHtml<String>>, PageError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
     fn render_with_status<T: ctx::PageContext + serde::Serialize + std::fmt::Debug>(
         status: Status,
         context: T,
+        manage_token: &Option<String>,
         renderer: &Renderer,
     ) -> Result<status::Custom<RawHtml<String>>, PageError> {
         Ok(status::Custom(
             status,
-            RawHtml(renderer.render(context, &[])),
+            RawHtml(renderer.render_with_data(context, ("manage_token", manage_token), &[])),
         ))
     }
 
-    match action::get_clip(shortcode.clone().into(), database.get_pool()).await {
+    let manage_token = cookies
+        .get_private(&manage_token_cookie(&shortcode))
+        .map(|cookie| {
+            cookies.remove_private(Cookie::build(cookie.name().to_owned()).path("/clip"));
+            cookie.value().to_owned()
+        });
+
+    let req = service::ask::GetClip {
+        unlock: UnlockToken::claim(cookies, &shortcode),
+        ..shortcode.clone().into()
+    };
+
+    match action::get_clip(req, database.get_pool()).await {
         Ok(clip) => {
-            hit_counter.hit(shortcode.clone(), 1);
-            let context = ctx::ViewClip::new(clip);
-            render_with_status(Status::Ok, context, renderer)
+            hit_counter.hit_clip(&clip);
+            let context = view_clip(clip, highlighter);
+            render_with_status(Status::Ok, context, &manage_token, renderer)
         }
         Err(e) => match e {
             ServiceError::PermissionError(_) => {
                 let context = ctx::PasswordRequired::new(shortcode);
-                render_with_status(Status::Unauthorized, context, renderer)
+                render_with_status(Status::Unauthorized, context, &manage_token, renderer)
             }
             ServiceError::NotFound => Err(PageError::NotFound("clip not found".to_owned())),
             _ => Err(PageError::Internal("server error".to_owned())),
@@ -113,29 +425,33 @@ pub async fn get_clip(
 }
 
 /// Route to submit a [`Password`](crate::domain::clip::field::Password) for a password-protected [`Clip`](crate::Clip).
+// Rocket hands every guard in as an argument
+#[allow(clippy::too_many_arguments)]
 #[rocket::post("/clip/<shortcode>", data = "<form>")]
 pub async fn submit_clip_password(
     cookies: &CookieJar<'_>,
     form: Form<Contextual<'_, form::GetPasswordProtectedClip>>,
-    shortcode: ShortCode,
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
     hit_counter: &State<HitCounter>,
+    highlighter: &State<Highlighter>,
     database: &State<AppDatabase>,
     renderer: &State<Renderer<'_>>,
 ) -> Result<RawHtml<String>, PageError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
     if let Some(form) = &form.value {
         let req = service::ask::GetClip {
             shortcode: shortcode.clone(),
             password: form.password.clone(),
+            unlock: None,
+            reader: None,
         };
 
         match action::get_clip(req, database.get_pool()).await {
             Ok(clip) => {
-                hit_counter.hit(shortcode.clone(), 1);
-                let context = ctx::ViewClip::new(clip);
-                cookies.add(Cookie::new(
-                    PASSWORD_COOKIE,
-                    form.password.clone().into_inner().unwrap_or_default(),
-                ));
+                hit_counter.hit_clip(&clip);
+                UnlockToken::new(&clip).issue(cookies);
+                let context = view_clip(clip, highlighter);
                 Ok(RawHtml(renderer.render(context, &[])))
             }
             Err(e) => match e {
@@ -157,39 +473,319 @@ pub async fn submit_clip_password(
 }
 
 /// Route to get just the [`Content`](crate::domain::clip::field::Content) of a [`Clip`](crate::Clip).
+///
+/// Encrypted clips are returned as the stored ciphertext, the key never reaches the server.
 #[rocket::get("/clip/raw/<shortcode>")]
 pub async fn get_raw_clip(
     cookies: &CookieJar<'_>,
-    shortcode: ShortCode,
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
     hit_counter: &State<HitCounter>,
     database: &State<AppDatabase>,
-) -> Result<status::Custom<String>, Status> {
-    use crate::domain::clip::field::Password;
-
+) -> Result<status::Custom<RawClip>, Status> {
+    let shortcode = ShortCode::parse(shortcode, format).map_err(|_| Status::NotFound)?;
     let req = service::ask::GetClip {
-        shortcode: shortcode.clone(),
-        password: cookies
-            .get(PASSWORD_COOKIE)
-            .map(|cookie| cookie.value())
-            .and_then(|raw_password| Password::new(raw_password.to_string()).ok())
-            .unwrap_or_default(),
+        unlock: UnlockToken::claim(cookies, &shortcode),
+        ..shortcode.clone().into()
     };
 
     match action::get_clip(req, database.get_pool()).await {
         Ok(clip) => {
-            hit_counter.hit(shortcode.clone(), 1);
-            Ok(status::Custom(Status::Ok, clip.content.into_inner()))
+            hit_counter.hit_clip(&clip);
+            let raw = match clip.attachment {
+                Some(attachment) => RawClip::File(attachment),
+                None => RawClip::Text(clip.content.into_inner()),
+            };
+            Ok(status::Custom(Status::Ok, raw))
         }
         Err(e) => match e {
-            ServiceError::PermissionError(msg) => Ok(status::Custom(Status::Unauthorized, msg)),
+            ServiceError::PermissionError(msg) => {
+                Ok(status::Custom(Status::Unauthorized, RawClip::Text(msg)))
+            }
             ServiceError::NotFound => Err(Status::NotFound),
             _ => Err(Status::InternalServerError),
         },
     }
 }
 
+/// Route to ask for the management token of a [`Clip`](crate::Clip) before editing it.
+///
+/// Ranked below [`get_raw_clip`], which would otherwise collide for `/clip/raw/edit`.
+#[rocket::get("/clip/<shortcode>/edit", rank = 2)]
+pub fn edit_clip(
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<RawHtml<String>, PageError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    let context = ctx::EditClip::new(shortcode, field::ManageToken::default(), None);
+    Ok(RawHtml(renderer.render(context, &[])))
+}
+
+/// Route to check the management token and show the [`Clip`](crate::Clip) for editing.
+#[rocket::post("/clip/<shortcode>/edit", data = "<form>", rank = 2)]
+pub async fn submit_edit_token(
+    form: Form<form::ManageClip>,
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    database: &State<AppDatabase>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<status::Custom<RawHtml<String>>, PageError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    let token = form.into_inner().token;
+    let credential = service::ask::Credential::ManageToken(token.clone());
+
+    match action::get_owned_clip(&shortcode, credential, database.get_pool()).await {
+        Ok(clip) if clip.encrypted.is_set() => {
+            let context = ctx::EditClip::new(shortcode, field::ManageToken::default(), None);
+            Ok(status::Custom(
+                Status::BadRequest,
+                RawHtml(renderer.render(context, &["Encrypted clips can't be edited"])),
+            ))
+        }
+        Ok(clip) => {
+            let context = ctx::EditClip::new(shortcode, token, Some(clip));
+            Ok(status::Custom(
+                Status::Ok,
+                RawHtml(renderer.render(context, &[])),
+            ))
+        }
+        Err(ServiceError::PermissionError(msg)) => {
+            let context = ctx::EditClip::new(shortcode, field::ManageToken::default(), None);
+            Ok(status::Custom(
+                Status::Unauthorized,
+                RawHtml(renderer.render(context, &[msg.as_str()])),
+            ))
+        }
+        Err(ServiceError::NotFound) => Err(PageError::NotFound("Clip not found".to_owned())),
+        Err(_) => Err(PageError::Internal("server error".to_owned())),
+    }
+}
+
+/// Route to save an edited [`Clip`](crate::Clip), authorized by its management token.
+#[rocket::post("/clip/<shortcode>/edit/save", data = "<form>")]
+pub async fn save_edited_clip(
+    form: Form<Contextual<'_, form::EditClip>>,
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    database: &State<AppDatabase>,
+    limits: &State<Limits>,
+    compression: &State<Compression>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<Redirect, (Status, RawHtml<String>)> {
+    let shortcode = ShortCode::parse(shortcode, format)
+        .map_err(|_| (Status::NotFound, RawHtml("clip not found".to_owned())))?;
+    // On errors, start over by asking for the management token
+    let render_error = |status, errors: &[&str]| {
+        let context = ctx::EditClip::new(shortcode.clone(), field::ManageToken::default(), None);
+        (status, RawHtml(renderer.render(context, errors)))
+    };
+
+    let value = {
+        let form = form.into_inner();
+        match form.value {
+            Some(value) => value,
+            None => {
+                let (status, errors) = form_errors(&form.context);
+                let errors: Vec<&str> = errors.iter().map(String::as_str).collect();
+                return Err(render_error(status, &errors));
+            }
+        }
+    };
+
+    let credential = service::ask::Credential::ManageToken(value.token);
+    let result =
+        match action::get_owned_clip(&shortcode, credential.clone(), database.get_pool()).await {
+            Ok(clip) => {
+                let req = service::ask::UpdateClip {
+                    content: value.content,
+                    title: value.title,
+                    expires: value.expires,
+                    password: if value.remove_password {
+                        field::Password::default()
+                    } else if value.password.has_password() {
+                        value.password
+                    } else {
+                        clip.password
+                    },
+                    shortcode: shortcode.clone(),
+                };
+                action::update_clip(req, credential, limits, compression, database.get_pool()).await
+            }
+            Err(e) => Err(e),
+        };
+
+    match result {
+        Ok(clip) => Ok(Redirect::to(uri!(get_clip(
+            shortcode = clip.shortcode.as_str()
+        )))),
+        Err(ServiceError::PermissionError(msg)) => {
+            Err(render_error(Status::Unauthorized, &[msg.as_str()]))
+        }
+        Err(ServiceError::NotFound) => Err(render_error(Status::NotFound, &["Clip not found"])),
+        Err(ServiceError::Clip(e @ ClipError::ContentTooLarge { .. })) => Err(render_error(
+            Status::PayloadTooLarge,
+            &[e.to_string().as_str()],
+        )),
+        Err(ServiceError::Clip(e)) => {
+            Err(render_error(Status::BadRequest, &[e.to_string().as_str()]))
+        }
+        Err(e) => {
+            eprintln!("internal error: {}", e);
+            Err(render_error(
+                Status::InternalServerError,
+                &["A server error occurred. Please try again"],
+            ))
+        }
+    }
+}
+
+/// Route to ask for the management token of a [`Clip`](crate::Clip) before showing its history.
+#[rocket::get("/clip/<shortcode>/history", rank = 2)]
+pub fn clip_history(
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<RawHtml<String>, PageError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    Ok(RawHtml(
+        renderer.render(ctx::ClipHistory::locked(shortcode), &[]),
+    ))
+}
+
+/// Route to show a line diff between two versions of a [`Clip`](crate::Clip), authorized by
+/// its management token.
+///
+/// Without `from` and `to`, the last revision is compared with the current version.
+#[rocket::post("/clip/<shortcode>/history", data = "<form>", rank = 2)]
+pub async fn submit_clip_history(
+    form: Form<form::ClipHistory>,
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    database: &State<AppDatabase>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<status::Custom<RawHtml<String>>, PageError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    use crate::domain::diff;
+
+    let form = form.into_inner();
+    let credential = service::ask::Credential::ManageToken(form.token.clone());
+    let (clip, revisions) =
+        match action::get_clip_history(&shortcode, credential, database.get_pool()).await {
+            Ok(history) => history,
+            Err(ServiceError::PermissionError(msg)) => {
+                let context = ctx::ClipHistory::locked(shortcode);
+                return Ok(status::Custom(
+                    Status::Unauthorized,
+                    RawHtml(renderer.render(context, &[msg.as_str()])),
+                ));
+            }
+            Err(ServiceError::NotFound) => {
+                return Err(PageError::NotFound("Clip not found".to_owned()))
+            }
+            Err(_) => return Err(PageError::Internal("server error".to_owned())),
+        };
+
+    let mut contents: Vec<String> = revisions
+        .into_iter()
+        .map(|revision| revision.content.into_inner())
+        .collect();
+    contents.push(clip.content.into_inner());
+    let current = contents.len() as u32;
+    let to = form.to.unwrap_or(current);
+    let from = form.from.unwrap_or_else(|| to.saturating_sub(1).max(1));
+    let content = |version: u32| {
+        version
+            .checked_sub(1)
+            .and_then(|index| contents.get(index as usize))
+            .cloned()
+    };
+    let (old, new) = match (content(from), content(to)) {
+        (Some(old), Some(new)) => (old, new),
+        _ => return Err(PageError::NotFound("Revision not found".to_owned())),
+    };
+    // Diffing large clips takes a while, keep it away from the async workers
+    let lines = rocket::tokio::task::spawn_blocking(move || diff::diff(&old, &new))
+        .await
+        .map_err(|_| PageError::Internal("server error".to_owned()))?;
+
+    let context = ctx::ClipHistory::new(
+        shortcode,
+        form.token,
+        (1..=current).collect(),
+        from,
+        to,
+        lines,
+    );
+    Ok(status::Custom(
+        Status::Ok,
+        RawHtml(renderer.render(context, &[])),
+    ))
+}
+
+/// Route to ask for the management token of a [`Clip`](crate::Clip) before deleting it.
+#[rocket::get("/clip/<shortcode>/delete", rank = 2)]
+pub fn delete_clip(
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<RawHtml<String>, PageError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    let context = ctx::DeleteClip::new(shortcode, false);
+    Ok(RawHtml(renderer.render(context, &[])))
+}
+
+/// Route to delete a [`Clip`](crate::Clip), authorized by its management token.
+#[rocket::post("/clip/<shortcode>/delete", data = "<form>", rank = 2)]
+pub async fn submit_delete_clip(
+    form: Form<form::ManageClip>,
+    shortcode: &str,
+    format: &State<ShortCodeFormat>,
+    database: &State<AppDatabase>,
+    renderer: &State<Renderer<'_>>,
+) -> Result<status::Custom<RawHtml<String>>, PageError> {
+    let shortcode = parse_shortcode(shortcode, format)?;
+    let credential = service::ask::Credential::ManageToken(form.into_inner().token);
+    let req = service::ask::DeleteClip::from(shortcode.clone());
+
+    match action::delete_clip(req, credential, database.get_pool()).await {
+        Ok(()) => {
+            let context = ctx::DeleteClip::new(shortcode, true);
+            Ok(status::Custom(
+                Status::Ok,
+                RawHtml(renderer.render(context, &[])),
+            ))
+        }
+        Err(ServiceError::PermissionError(msg)) => {
+            let context = ctx::DeleteClip::new(shortcode, false);
+            Ok(status::Custom(
+                Status::Unauthorized,
+                RawHtml(renderer.render(context, &[msg.as_str()])),
+            ))
+        }
+        Err(ServiceError::NotFound) => Err(PageError::NotFound("Clip not found".to_owned())),
+        Err(_) => Err(PageError::Internal("server error".to_owned())),
+    }
+}
+
 pub fn routes() -> Vec<rocket::Route> {
-    rocket::routes![home, get_clip, new_clip, submit_clip_password, get_raw_clip]
+    rocket::routes![
+        home,
+        recent,
+        search,
+        get_clip,
+        new_clip,
+        upload_clip,
+        submit_clip_password,
+        get_raw_clip,
+        clip_history,
+        submit_clip_history,
+        edit_clip,
+        submit_edit_token,
+        save_edited_clip,
+        delete_clip,
+        submit_delete_clip
+    ]
 }
 
 pub mod catcher {
@@ -221,24 +817,503 @@ pub mod catcher {
 
 #[cfg(test)]
 pub mod test {
-    use rocket::form::FromFormField;
-    use rocket::http::Status;
+    use rocket::http::{ContentType, Status};
 
+    use crate::data::compression::Compression;
     use crate::data::AppDatabase;
+    use crate::domain::clip::field::{self, ShortCodeFormat};
+    use crate::domain::clip::limits::Limits;
+    use crate::service::{action, ask};
+    #[allow(unused_imports)]
     use crate::test::async_runtime;
-    use crate::web::test::{client, init_test_client};
+    #[allow(unused_imports)]
+    use crate::web::test::{ask_new_clip, client, create_clip, init_test_client};
 
     #[test]
     fn test_get_home() {
-        let (_, client) = init_test_client();
+        let (_rt, client) = init_test_client();
         let response = client.get("/").dispatch();
         assert_eq!(response.status(), Status::Ok);
     }
 
+    #[test]
+    fn test_search_page() {
+        let (_rt, client) = init_test_client();
+        assert_eq!(client.get("/search").dispatch().status(), Status::Ok);
+        let response = client.get("/search?q=nothing%22here").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_string().unwrap().contains("No clips found"));
+    }
+
+    #[test]
+    fn test_recent_page() {
+        let (_rt, client) = init_test_client();
+        let post = |body: &str| {
+            client
+                .post("/")
+                .header(ContentType::Form)
+                .body(body)
+                .dispatch()
+                .status()
+        };
+        let clip = "content=snippet&expires=&password=&max_hits=&language=";
+        assert_eq!(
+            post(&format!("{}&title=team+wall&visibility=public", clip)),
+            Status::SeeOther
+        );
+        assert_eq!(post(&format!("{}&title=by+link", clip)), Status::SeeOther);
+        // Web visitors have no API key to read a private clip with
+        assert_eq!(
+            post(&format!("{}&title=mine&visibility=private", clip)),
+            Status::BadRequest
+        );
+
+        let page = client.get("/recent").dispatch().into_string().unwrap();
+        assert!(page.contains("team wall"));
+        assert!(!page.contains("by link"));
+    }
+
     #[test]
     fn test_error_on_missing_clip() {
-        let (_, client) = init_test_client();
+        let (_rt, client) = init_test_client();
         let response = client.get("/clip/adf").dispatch();
         assert_eq!(response.status(), Status::NotFound);
     }
+
+    #[test]
+    fn test_shortcode_outside_format() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        // Generated in a format the server isn't configured with
+        let format = ShortCodeFormat::new(10, "ABCDEFGHIJ", false).unwrap();
+        let clip = rt
+            .block_on(action::new_clip(
+                ask_new_clip("uppercase"),
+                &Limits::default(),
+                &format,
+                &Compression::default(),
+                pool,
+            ))
+            .unwrap();
+
+        let shortcode = clip.shortcode.as_str();
+        for uri in [
+            format!("/clip/{}", shortcode),
+            format!("/clip/raw/{}", shortcode),
+        ] {
+            assert_eq!(client.get(uri).dispatch().status(), Status::NotFound);
+        }
+    }
+
+    #[test]
+    fn test_unlock_token_is_scoped_to_clip() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let new_clip = || ask::NewClip {
+            password: field::Password::new("secret".to_owned()),
+            ..ask_new_clip("secret content")
+        };
+        let first = create_clip(&rt, pool, new_clip());
+        let second = create_clip(&rt, pool, new_clip());
+
+        let raw_first = format!("/clip/raw/{}", first.shortcode.as_str());
+        let raw_second = format!("/clip/raw/{}", second.shortcode.as_str());
+        assert_eq!(
+            client.get(&raw_first).dispatch().status(),
+            Status::Unauthorized
+        );
+
+        let response = client
+            .post(format!("/clip/{}", first.shortcode.as_str()))
+            .header(ContentType::Form)
+            .body("password=secret")
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response
+            .cookies()
+            .iter()
+            .all(|cookie| !cookie.value().contains("secret")));
+
+        let response = client.get(&raw_first).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().unwrap(), "secret content");
+        assert_eq!(
+            client.get(&raw_second).dispatch().status(),
+            Status::Unauthorized
+        );
+    }
+
+    #[test]
+    fn test_unlock_token_is_bound_to_password_and_clip() {
+        use crate::data::query;
+
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let shortcode = crate::ShortCode::from("reused");
+        let new_clip = || ask::NewClip {
+            password: field::Password::new("secret".to_owned()),
+            shortcode: Some(shortcode.clone()),
+            ..ask_new_clip("secret content")
+        };
+        let unlock = || {
+            client
+                .post("/clip/reused")
+                .header(ContentType::Form)
+                .body("password=secret")
+                .dispatch()
+                .status()
+        };
+        let raw = || client.get("/clip/raw/reused").dispatch().status();
+
+        create_clip(&rt, pool, new_clip());
+        assert_eq!(unlock(), Status::Ok);
+        assert_eq!(raw(), Status::Ok);
+
+        // Changing the password locks the clip again
+        let password = field::Password::new("changed".to_owned()).hash().unwrap();
+        rt.block_on(query::update_password(&shortcode, password, pool))
+            .unwrap();
+        assert_eq!(raw(), Status::Unauthorized);
+
+        // A new clip with the shortcode of a deleted one isn't unlocked either
+        rt.block_on(action::purge_clip(&shortcode, pool)).unwrap();
+        create_clip(&rt, pool, new_clip());
+        assert_eq!(unlock(), Status::Ok);
+        rt.block_on(action::purge_clip(&shortcode, pool)).unwrap();
+        create_clip(&rt, pool, new_clip());
+        assert_eq!(raw(), Status::Unauthorized);
+    }
+
+    #[test]
+    fn test_burn_after_reading() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let req = ask::NewClip {
+            burn_after_reading: field::BurnAfterReading::new(true),
+            ..ask_new_clip("one time secret")
+        };
+        let clip = create_clip(&rt, pool, req);
+
+        let raw = format!("/clip/raw/{}", clip.shortcode.as_str());
+        let response = client.get(&raw).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().unwrap(), "one time secret");
+        assert_eq!(client.get(&raw).dispatch().status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_max_hits() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let req = ask::NewClip {
+            max_hits: field::MaxHits::new(2).unwrap(),
+            ..ask_new_clip("limited")
+        };
+        let clip = create_clip(&rt, pool, req);
+
+        let raw = format!("/clip/raw/{}", clip.shortcode.as_str());
+        assert_eq!(client.get(&raw).dispatch().status(), Status::Ok);
+        assert_eq!(client.get(&raw).dispatch().status(), Status::Ok);
+        assert_eq!(client.get(&raw).dispatch().status(), Status::NotFound);
+
+        let purged = rt.block_on(action::delete_expires(pool)).unwrap();
+        assert_eq!(purged, 1);
+    }
+
+    #[test]
+    fn test_manage_token() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let token = field::ManageToken::generate();
+        let req = ask::NewClip {
+            manage_token: token.clone(),
+            ..ask_new_clip("original")
+        };
+        let clip = create_clip(&rt, pool, req);
+        let shortcode = clip.shortcode.as_str().to_owned();
+        let token = token.into_inner().unwrap();
+
+        let response = client
+            .post(format!("/clip/{}/edit", shortcode))
+            .header(ContentType::Form)
+            .body("token=wrong")
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        let response = client
+            .post(format!("/clip/{}/edit/save", shortcode))
+            .header(ContentType::Form)
+            .body(format!(
+                "token={}&content=edited&title=&expires=&password=",
+                token
+            ))
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        let raw = format!("/clip/raw/{}", shortcode);
+        assert_eq!(client.get(&raw).dispatch().into_string().unwrap(), "edited");
+        let page = client.get(format!("/clip/{}", shortcode)).dispatch();
+        assert!(page.into_string().unwrap().contains("/edit"));
+
+        let response = client
+            .post(format!("/clip/{}/delete", shortcode))
+            .header(ContentType::Form)
+            .body("token=wrong")
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        let response = client
+            .post(format!("/clip/{}/delete", shortcode))
+            .header(ContentType::Form)
+            .body(format!("token={}", token))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(client.get(&raw).dispatch().status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_manage_token_shown_once_and_remove_password() {
+        let (_rt, client) = init_test_client();
+        let response = client
+            .post("/")
+            .header(ContentType::Form)
+            .body("content=guarded&title=&expires=&password=secret&max_hits=&language=")
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        // The token travels in an encrypted cookie, never as plain text
+        assert!(response.cookies().get("_flash").is_none());
+        let location = response.headers().get_one("Location").unwrap().to_owned();
+        let shortcode = location.trim_start_matches("/clip/").to_owned();
+
+        let page = client.get(&location).dispatch().into_string().unwrap();
+        let token = page
+            .split("<pre class=\"mt-2\">")
+            .nth(1)
+            .and_then(|rest| rest.split("</pre>").next())
+            .unwrap()
+            .to_owned();
+        let page = client.get(&location).dispatch().into_string().unwrap();
+        assert!(!page.contains(&token));
+
+        let raw = format!("/clip/raw/{}", shortcode);
+        assert_eq!(client.get(&raw).dispatch().status(), Status::Unauthorized);
+        let response = client
+            .post(format!("/clip/{}/edit/save", shortcode))
+            .header(ContentType::Form)
+            .body(format!(
+                "token={}&content=guarded&title=&expires=&password=&remove_password=on",
+                token
+            ))
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        assert_eq!(client.get(&raw).dispatch().status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_clip_history() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let token = field::ManageToken::generate();
+        let req = ask::NewClip {
+            manage_token: token.clone(),
+            ..ask_new_clip("kept\nold line")
+        };
+        let clip = create_clip(&rt, pool, req);
+        let req = ask::UpdateClip {
+            content: field::Content::new("kept\nnew line").unwrap(),
+            title: field::Title::default(),
+            expires: field::Expires::default(),
+            password: field::Password::default(),
+            shortcode: clip.shortcode.clone(),
+        };
+        rt.block_on(action::update_clip(
+            req,
+            ask::Credential::ManageToken(token.clone()),
+            &Limits::default(),
+            &Compression::default(),
+            pool,
+        ))
+        .unwrap();
+
+        let uri = format!("/clip/{}/history", clip.shortcode.as_str());
+        let history = |body: String| {
+            client
+                .post(&uri)
+                .header(ContentType::Form)
+                .body(body)
+                .dispatch()
+        };
+        // Readers of the clip don't get to see its earlier versions
+        assert_eq!(client.get(&uri).dispatch().status(), Status::Ok);
+        assert_eq!(
+            history("token=wrong".to_owned()).status(),
+            Status::Unauthorized
+        );
+
+        let token = token.into_inner().unwrap();
+        let response = history(format!("token={}", token));
+        assert_eq!(response.status(), Status::Ok);
+        let page = response.into_string().unwrap();
+        assert!(page.contains("diff-removed\">- old line"));
+        assert!(page.contains("diff-added\">+ new line"));
+
+        let response = history(format!("token={}&from=1&to=5", token));
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_highlighted_clip() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let req = ask_new_clip("SELECT * FROM clips WHERE hits > 1;");
+        let clip = create_clip(&rt, pool, req);
+        assert_eq!(clip.language.as_deref(), Some("sql"));
+
+        let response = client
+            .get(format!("/clip/{}", clip.shortcode.as_str()))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let page = response.into_string().unwrap();
+        assert!(page.contains("<span style="));
+        assert!(page.contains("&gt;"));
+    }
+
+    #[test]
+    fn test_markdown_clip() {
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let source = "# Restart\n\n- [ ] stop <script>alert(1)</script>";
+        let req = ask::NewClip {
+            language: field::Language::new("md".to_owned()).unwrap(),
+            ..ask_new_clip(source)
+        };
+        let clip = create_clip(&rt, pool, req);
+
+        let page = client
+            .get(format!("/clip/{}", clip.shortcode.as_str()))
+            .dispatch()
+            .into_string()
+            .unwrap();
+        assert!(page.contains("<h1>Restart</h1>"));
+        assert!(!page.contains("<script>alert"));
+
+        let raw = client
+            .get(format!("/clip/raw/{}", clip.shortcode.as_str()))
+            .dispatch()
+            .into_string()
+            .unwrap();
+        assert_eq!(raw, source);
+    }
+
+    #[test]
+    fn test_upload_clip() {
+        let (_rt, client) = init_test_client();
+        let body = "--BOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"notes.zip\"\r\n\
+            Content-Type: application/zip\r\n\r\n\
+            PK\x03\x04archive\r\n\
+            --BOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+            Release notes\r\n\
+            --BOUNDARY--\r\n";
+        let response = client
+            .post("/upload")
+            .header(
+                ContentType::new("multipart", "form-data").with_params(("boundary", "BOUNDARY")),
+            )
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        let location = response.headers().get_one("Location").unwrap().to_owned();
+        let shortcode = location.rsplit('/').next().unwrap();
+
+        let page = client.get(&location).dispatch().into_string().unwrap();
+        assert!(page.contains("Release notes"));
+        assert!(page.contains("download=\"notes.zip\""));
+
+        let response = client.get(format!("/clip/raw/{}", shortcode)).dispatch();
+        assert_eq!(response.content_type(), Some(ContentType::ZIP));
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some("attachment; filename*=UTF-8''notes.zip")
+        );
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let (_rt, client) = init_test_client();
+        let post = |body: String| {
+            client
+                .post("/")
+                .header(ContentType::Form)
+                .body(body)
+                .dispatch()
+        };
+
+        let response = post(format!("content=hello&title={}", "t".repeat(201)));
+        assert_eq!(response.status(), Status::BadRequest);
+        assert!(response
+            .into_string()
+            .unwrap()
+            .contains("title is too long, the limit is 200 characters"));
+
+        let content = "a".repeat(Limits::DEFAULT_CONTENT + 1);
+        let response = post(format!("content={}", content));
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+        assert!(response
+            .into_string()
+            .unwrap()
+            .contains("content is too large"));
+    }
+
+    #[test]
+    fn test_encrypted_clip() {
+        use crate::domain::encryption::EncryptionKey;
+
+        let (rt, client) = init_test_client();
+        let pool = client.rocket().state::<AppDatabase>().unwrap().get_pool();
+        let key = EncryptionKey::generate();
+        let ciphertext = key.encrypt("fn main() {}");
+        let token = field::ManageToken::generate();
+        let req = ask::NewClip {
+            encrypted: field::Encrypted::new(true),
+            manage_token: token.clone(),
+            ..ask_new_clip(&ciphertext)
+        };
+        let clip = create_clip(&rt, pool, req);
+        assert!(!clip.language.is_set());
+
+        let page = client
+            .get(format!("/clip/{}", clip.shortcode.as_str()))
+            .dispatch()
+            .into_string()
+            .unwrap();
+        assert!(page.contains("id=\"clip-ciphertext\""));
+        assert!(!page.contains("clip-highlighted"));
+        assert!(!page.contains("/edit"));
+
+        let raw = client
+            .get(format!("/clip/raw/{}", clip.shortcode.as_str()))
+            .dispatch()
+            .into_string()
+            .unwrap();
+        assert_eq!(raw, ciphertext);
+        assert_eq!(key.decrypt(&raw).unwrap(), "fn main() {}");
+
+        // Saving plaintext would replace the ciphertext, even with the management token
+        let response = client
+            .post(format!("/clip/{}/edit/save", clip.shortcode.as_str()))
+            .header(ContentType::Form)
+            .body(format!(
+                "token={}&content=plaintext&title=&expires=&password=",
+                token.into_inner().unwrap()
+            ))
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+        let raw = client
+            .get(format!("/clip/raw/{}", clip.shortcode.as_str()))
+            .dispatch()
+            .into_string()
+            .unwrap();
+        assert_eq!(raw, ciphertext);
+    }
 }
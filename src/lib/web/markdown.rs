@@ -0,0 +1,51 @@
+use pulldown_cmark::{html, Options, Parser};
+
+use crate::domain::clip::field::Content;
+
+/// Render Markdown content as HTML which is safe to embed in a page.
+///
+/// Tables, task lists and strikethrough are supported. The generated HTML is sanitized, so raw
+/// HTML in the content can't inject scripts, and task list checkboxes are always disabled.
+pub fn render(content: &Content) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES;
+    let parser = Parser::new_ext(content.as_str(), options);
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+
+    ammonia::Builder::default()
+        .add_tags(&["input"])
+        .add_tag_attributes("input", &["checked"])
+        .set_tag_attribute_value("input", "type", "checkbox")
+        .set_tag_attribute_value("input", "disabled", "")
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::render;
+    use crate::domain::clip::field::Content;
+
+    #[test]
+    fn test_render_markdown() {
+        let content = "# Runbook\n\n- [x] backup\n- [ ] restore\n\n| a | b |\n|---|---|\n| 1 | 2 |";
+        let html = render(&Content::new(content).unwrap());
+        assert!(html.contains("<h1>Runbook</h1>"));
+        assert!(html.contains("<table>"));
+        assert!(html.contains("type=\"checkbox\""));
+        assert!(html.contains("disabled"));
+    }
+
+    #[test]
+    fn test_render_sanitizes() {
+        let content =
+            "<script>alert(1)</script>\n\n[x](javascript:alert(1)) <img src=x onerror=alert(1)>";
+        let html = render(&Content::new(content).unwrap());
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("onerror"));
+    }
+}
@@ -1,3 +1,4 @@
+#[allow(unused_imports)]
 use handlebars::template;
 
 use crate::web::ctx;
@@ -20,6 +21,7 @@ impl<'a> Renderer<'a> {
     }
 
     // Turn a serializable structure into json Value
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn convert_to_value<S>(serializable: &S) -> serde_json::Value
     where
         S: serde::Serialize + std::fmt::Debug,
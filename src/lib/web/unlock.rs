@@ -0,0 +1,107 @@
+use chrono::{Duration, Utc};
+use rocket::http::{Cookie, CookieJar, SameSite};
+
+use crate::data::DbId;
+use crate::service::ask;
+use crate::{Clip, ShortCode};
+
+/// Prefix of the per-clip cookie which holds an [`UnlockToken`].
+pub const UNLOCK_COOKIE_PREFIX: &str = "unlock-";
+
+/// How long an [`UnlockToken`] stays valid after the password was entered.
+pub const UNLOCK_TOKEN_TTL_SECS: i64 = 60 * 60;
+
+/// Proof that the password of a single clip was entered recently.
+///
+/// The token is stored in an encrypted and authenticated (private) cookie, named after the
+/// shortcode it belongs to. It carries the id of the clip and a digest of its password, so it
+/// neither unlocks a new clip which reuses the shortcode nor survives a password change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnlockToken {
+    shortcode: ShortCode,
+    clip_id: DbId,
+    password_digest: String,
+    expires: i64,
+}
+
+impl UnlockToken {
+    pub fn new(clip: &Clip) -> Self {
+        Self {
+            shortcode: clip.shortcode.clone(),
+            clip_id: clip.clip_id.clone().into_inner(),
+            password_digest: clip.password.digest().unwrap_or_default(),
+            expires: (Utc::now() + Duration::seconds(UNLOCK_TOKEN_TTL_SECS)).timestamp(),
+        }
+    }
+
+    pub fn cookie_name(shortcode: &ShortCode) -> String {
+        format!("{}{}", UNLOCK_COOKIE_PREFIX, shortcode.as_str())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires <= Utc::now().timestamp()
+    }
+
+    /// Add the token to the client as a private cookie.
+    pub fn issue(self, cookies: &CookieJar<'_>) {
+        let mut cookie = Cookie::new(
+            Self::cookie_name(&self.shortcode),
+            format!("{}:{}:{}", self.clip_id, self.password_digest, self.expires),
+        );
+        cookie.set_path("/");
+        cookie.set_http_only(true);
+        cookie.set_same_site(SameSite::Lax);
+        cookie.set_max_age(rocket::time::Duration::seconds(UNLOCK_TOKEN_TTL_SECS));
+        cookies.add_private(cookie);
+    }
+
+    /// Retrieve a still valid token for the given [`ShortCode`] from the client cookies.
+    pub fn from_cookies(cookies: &CookieJar<'_>, shortcode: &ShortCode) -> Option<Self> {
+        let cookie = cookies.get_private(&Self::cookie_name(shortcode))?;
+        let mut parts = cookie.value().splitn(3, ':');
+        let token = Self {
+            shortcode: shortcode.clone(),
+            clip_id: parts.next()?.parse().ok()?,
+            password_digest: parts.next()?.to_owned(),
+            expires: parts.next()?.parse().ok()?,
+        };
+        if token.is_expired() {
+            None
+        } else {
+            Some(token)
+        }
+    }
+
+    /// Retrieve the claim of a still valid token, for the service layer to check against
+    /// the clip.
+    pub fn claim(cookies: &CookieJar<'_>, shortcode: &ShortCode) -> Option<ask::Unlocked> {
+        Self::from_cookies(cookies, shortcode).map(Into::into)
+    }
+}
+
+impl From<UnlockToken> for ask::Unlocked {
+    fn from(token: UnlockToken) -> Self {
+        Self {
+            clip_id: token.clip_id,
+            password_digest: token.password_digest,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UnlockToken;
+    use crate::data::DbId;
+    use crate::ShortCode;
+
+    #[test]
+    fn test_expired_token() {
+        let token = UnlockToken {
+            shortcode: ShortCode::from("abc"),
+            clip_id: DbId::new(),
+            password_digest: String::new(),
+            expires: 0,
+        };
+        assert!(token.is_expired());
+    }
+}
@@ -0,0 +1,68 @@
+// End-to-end encryption of clips, compatible with clipclient.
+//
+// Content is encrypted with AES-256-GCM. The stored ciphertext is the base64 encoded nonce
+// followed by the encrypted content, the key is URL safe base64 and only ever lives in the
+// fragment of the link, which browsers don't send to the server.
+var ClipE2E = (function () {
+  var NONCE_LENGTH = 12;
+
+  function toBase64(bytes) {
+    var binary = '';
+    for (var i = 0; i < bytes.length; i++) {
+      binary += String.fromCharCode(bytes[i]);
+    }
+    return btoa(binary);
+  }
+
+  function fromBase64(text) {
+    var binary = atob(text);
+    var bytes = new Uint8Array(binary.length);
+    for (var i = 0; i < binary.length; i++) {
+      bytes[i] = binary.charCodeAt(i);
+    }
+    return bytes;
+  }
+
+  function toBase64Url(bytes) {
+    return toBase64(bytes).replace(/\+/g, '-').replace(/\//g, '_').replace(/=+$/, '');
+  }
+
+  function fromBase64Url(text) {
+    var padded = text.replace(/-/g, '+').replace(/_/g, '/');
+    while (padded.length % 4) {
+      padded += '=';
+    }
+    return fromBase64(padded);
+  }
+
+  function importKey(raw, usage) {
+    return crypto.subtle.importKey('raw', raw, 'AES-GCM', false, [usage]);
+  }
+
+  // Resolves to { key, ciphertext }, with the key ready to be put into a link
+  function encrypt(content) {
+    var raw = crypto.getRandomValues(new Uint8Array(32));
+    var nonce = crypto.getRandomValues(new Uint8Array(NONCE_LENGTH));
+    return importKey(raw, 'encrypt').then(function (key) {
+      return crypto.subtle.encrypt({ name: 'AES-GCM', iv: nonce }, key,
+        new TextEncoder().encode(content));
+    }).then(function (encrypted) {
+      var data = new Uint8Array(NONCE_LENGTH + encrypted.byteLength);
+      data.set(nonce);
+      data.set(new Uint8Array(encrypted), NONCE_LENGTH);
+      return { key: toBase64Url(raw), ciphertext: toBase64(data) };
+    });
+  }
+
+  function decrypt(key, ciphertext) {
+    var data = fromBase64(ciphertext.trim());
+    return importKey(fromBase64Url(key), 'decrypt').then(function (key) {
+      return crypto.subtle.decrypt({ name: 'AES-GCM', iv: data.slice(0, NONCE_LENGTH) }, key,
+        data.slice(NONCE_LENGTH));
+    }).then(function (content) {
+      return new TextDecoder().decode(content);
+    });
+  }
+
+  return { encrypt: encrypt, decrypt: decrypt };
+})();